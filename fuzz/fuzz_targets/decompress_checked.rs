@@ -0,0 +1,33 @@
+//! The default decoder with `checked-decode` on arbitrary input. Anything it accepts must pass
+//! `block::validate`.
+#![no_main]
+
+use std::mem::MaybeUninit;
+
+use libfuzzer_sys::fuzz_target;
+use lz4_flex_wasm_simd::block::{
+    decompress_into, decompress_into_uninit, decompress_into_with_dict, decompress_partial_into,
+    validate,
+};
+
+fuzz_target!(|data: &[u8]| {
+    // The first two bytes pick the output size and how much of the input is dictionary.
+    let Some((&[size, dict_len], rest)) = data.split_first_chunk::<2>() else {
+        return;
+    };
+    let capacity = size as usize * 64;
+    let (dict, input) = rest.split_at((dict_len as usize).min(rest.len()));
+
+    let mut out = vec![0u8; capacity];
+    if let Ok(len) = decompress_into(input, &mut out) {
+        assert!(validate(input, len, b"").is_ok());
+    }
+    let mut uninit = vec![MaybeUninit::<u8>::uninit(); capacity];
+    if let Ok(out) = decompress_into_uninit(input, &mut uninit) {
+        assert!(validate(input, out.len(), b"").is_ok());
+    }
+    if let Ok(len) = decompress_into_with_dict(input, &mut out, dict) {
+        assert!(validate(input, len, dict).is_ok());
+    }
+    let _ = decompress_partial_into(input, &mut out, capacity / 2);
+});
@@ -0,0 +1,49 @@
+//! Arbitrary blocks into slice, uninitialized, partial and in-place outputs. Build with `--features safe`
+//! for the `safe-decode` decoder.
+#![no_main]
+
+use std::mem::MaybeUninit;
+
+use libfuzzer_sys::fuzz_target;
+use lz4_flex_wasm_simd::block::{
+    decompress_in_place, decompress_into, decompress_into_uninit, decompress_partial_into,
+};
+use lz4_flex_wasm_simd_fuzz::split_capacity;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((capacity, input)) = split_capacity(data) else {
+        return;
+    };
+
+    let mut out = vec![0u8; capacity];
+    let result = decompress_into(input, &mut out);
+    if let Ok(len) = result {
+        assert!(len <= capacity);
+    }
+
+    let mut uninit = vec![MaybeUninit::<u8>::uninit(); capacity];
+    let uninit_result = decompress_into_uninit(input, &mut uninit);
+    assert_eq!(
+        uninit_result.ok().map(|out| out.to_vec()),
+        result.as_ref().ok().map(|&len| out[..len].to_vec())
+    );
+
+    let target = capacity / 2;
+    let mut partial = vec![0u8; capacity];
+    if let Ok(len) = decompress_partial_into(input, &mut partial, target) {
+        assert!(len <= target);
+        if let Ok(full) = result {
+            assert_eq!(partial[..len], out[..len.min(full)]);
+        }
+    }
+
+    // The block after `capacity` bytes of room decodes in place to what fits that room.
+    let mut buf = vec![0u8; capacity + input.len()];
+    buf[capacity..].copy_from_slice(input);
+    let end = buf.len();
+    if let Ok(len) = decompress_in_place(&mut buf, capacity..end) {
+        let mut expected = vec![0u8; len];
+        assert_eq!(decompress_into(input, &mut expected).unwrap(), len);
+        assert_eq!(buf[..len], expected);
+    }
+});
@@ -0,0 +1,44 @@
+//! Arbitrary blocks against an external dictionary and the same bytes as an output prefix, plus
+//! a dictionary round trip.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lz4_flex_wasm_simd::block::{
+    compress_with_dict, decompress_into_with_dict, decompress_into_with_prefix,
+    decompress_with_dict,
+};
+use lz4_flex_wasm_simd_fuzz::split_capacity;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((capacity, rest)) = split_capacity(data) else {
+        return;
+    };
+    let Some((dict_len, rest)) = split_capacity(rest) else {
+        return;
+    };
+    let (dict, input) = rest.split_at(dict_len.min(rest.len()));
+
+    let mut out = vec![0u8; capacity];
+    let result = decompress_into_with_dict(input, &mut out, dict);
+    let vec_result = decompress_with_dict(input, capacity, dict);
+    assert_eq!(
+        vec_result.ok(),
+        result.as_ref().ok().map(|&len| out[..len].to_vec())
+    );
+
+    // The dictionary in front of the output gives the same result. Without `checked-decode` an
+    // offset past the window is clamped instead of rejected, which can land inside the prefix,
+    // so only blocks the dictionary decoder accepts are compared.
+    if let Ok(len) = result {
+        let mut prefixed = dict.to_vec();
+        prefixed.resize(dict.len() + capacity, 0);
+        let prefix_len = decompress_into_with_prefix(input, &mut prefixed, dict.len()).unwrap();
+        assert_eq!(prefixed[dict.len()..dict.len() + prefix_len], out[..len]);
+    }
+
+    let compressed = compress_with_dict(input, dict);
+    assert_eq!(
+        decompress_with_dict(&compressed, input.len(), dict).unwrap(),
+        input
+    );
+});
@@ -0,0 +1,60 @@
+//! Differential checks against `lz4_flex`, which shares the format but not the SIMD paths.
+#![no_main]
+
+use std::io::{Read, Write};
+
+use libfuzzer_sys::fuzz_target;
+use lz4_flex_wasm_simd::block::validate;
+use lz4_flex_wasm_simd::frame::{FrameDecoder, FrameEncoder};
+use lz4_flex_wasm_simd_fuzz::frame_info;
+
+/// Output cap for decoding arbitrary frames.
+const MAX_OUTPUT: u64 = 16 << 20;
+
+fuzz_target!(|data: &[u8]| {
+    // Each side decodes what the other encoded.
+    let ours = lz4_flex_wasm_simd::block::compress(data);
+    assert_eq!(
+        lz4_flex::block::decompress(&ours, data.len()).unwrap(),
+        data
+    );
+    let theirs = lz4_flex::block::compress(data);
+    assert_eq!(
+        lz4_flex_wasm_simd::block::decompress(&theirs, data.len()).unwrap(),
+        data
+    );
+
+    // The input as a block: well-formed blocks decode the same on both sides.
+    let capacity = data.len() * 4;
+    let ours = lz4_flex_wasm_simd::block::decompress(data, capacity);
+    let theirs = lz4_flex::block::decompress(data, capacity);
+    if let (Ok(ours), Ok(theirs)) = (&ours, &theirs) {
+        if validate(data, ours.len(), b"").is_ok() {
+            assert_eq!(ours, theirs);
+        }
+    }
+
+    if let Some((&selector, input)) = data.split_first() {
+        let mut encoder = FrameEncoder::with_frame_info(frame_info(selector), Vec::new());
+        encoder.write_all(input).unwrap();
+        let frame = encoder.finish().unwrap();
+        let mut out = Vec::new();
+        lz4_flex::frame::FrameDecoder::new(&frame[..])
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, input);
+    }
+
+    // The input as a frame.
+    let mut ours = Vec::new();
+    let ours_result = FrameDecoder::new(data)
+        .take(MAX_OUTPUT)
+        .read_to_end(&mut ours);
+    let mut theirs = Vec::new();
+    let theirs_result = lz4_flex::frame::FrameDecoder::new(data)
+        .take(MAX_OUTPUT)
+        .read_to_end(&mut theirs);
+    if ours_result.is_ok() && theirs_result.is_ok() {
+        assert_eq!(ours, theirs);
+    }
+});
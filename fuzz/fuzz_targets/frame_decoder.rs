@@ -0,0 +1,38 @@
+//! Arbitrary frames through `FrameDecoder`, both with `read` and `BufRead`.
+#![no_main]
+
+use std::io::{BufRead, Read};
+
+use libfuzzer_sys::fuzz_target;
+use lz4_flex_wasm_simd::frame::FrameDecoder;
+
+/// Output cap, so that frames of empty blocks with huge declared sizes stay cheap.
+const MAX_OUTPUT: u64 = 16 << 20;
+
+fuzz_target!(|data: &[u8]| {
+    let mut out = Vec::new();
+    let read = FrameDecoder::new(data)
+        .take(MAX_OUTPUT)
+        .read_to_end(&mut out);
+
+    let mut buffered = Vec::new();
+    let mut decoder = FrameDecoder::new(data);
+    let buf_read = loop {
+        match decoder.fill_buf() {
+            Ok([]) => break Ok(()),
+            Ok(buf) => {
+                let len = buf.len().min(7);
+                buffered.extend_from_slice(&buf[..len]);
+                decoder.consume(len);
+                if buffered.len() as u64 >= MAX_OUTPUT {
+                    break Ok(());
+                }
+            }
+            Err(err) => break Err(err),
+        }
+    };
+    if read.is_ok() && buf_read.is_ok() {
+        let len = out.len().min(buffered.len());
+        assert_eq!(out[..len], buffered[..len]);
+    }
+});
@@ -0,0 +1,52 @@
+//! `compress` → `decompress` for blocks and frames, and `compress_dest_size` into a capacity
+//! taken from the input.
+#![no_main]
+
+use std::io::{Read, Write};
+
+use libfuzzer_sys::fuzz_target;
+use lz4_flex_wasm_simd::block::{
+    compress, compress_dest_size, compress_into, compress_prepend_size, decompress,
+    decompress_size_prepended, get_maximum_output_size,
+};
+use lz4_flex_wasm_simd::frame::{FrameDecoder, FrameEncoder};
+use lz4_flex_wasm_simd_fuzz::frame_info;
+
+fuzz_target!(|data: &[u8]| {
+    let compressed = compress(data);
+    assert!(compressed.len() <= get_maximum_output_size(data.len()));
+    assert_eq!(decompress(&compressed, data.len()).unwrap(), data);
+    assert_eq!(
+        decompress_size_prepended(&compress_prepend_size(data)).unwrap(),
+        data
+    );
+
+    let mut exact = vec![0u8; get_maximum_output_size(data.len())];
+    let len = compress_into(data, &mut exact).unwrap();
+    assert_eq!(exact[..len], compressed);
+
+    let Some((&selector, input)) = data.split_first() else {
+        return;
+    };
+
+    // An exactly sized buffer, so that writes past the capacity trip the sanitizer.
+    let mut limited = vec![0u8; (selector as usize * 13) % (input.len() + 32)];
+    let (consumed, written) = compress_dest_size(input, &mut limited);
+    assert!(consumed <= input.len() && written <= limited.len());
+    if written > 0 {
+        assert_eq!(
+            decompress(&limited[..written], consumed).unwrap(),
+            &input[..consumed]
+        );
+    }
+
+    let mut encoder = FrameEncoder::with_frame_info(frame_info(selector), Vec::new());
+    // Uneven writes exercise the linked-block prefix handling.
+    for chunk in input.chunks(1 + selector as usize * 97) {
+        encoder.write_all(chunk).unwrap();
+    }
+    let frame = encoder.finish().unwrap();
+    let mut out = Vec::new();
+    FrameDecoder::new(&frame[..]).read_to_end(&mut out).unwrap();
+    assert_eq!(out, input);
+});
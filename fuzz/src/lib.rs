@@ -0,0 +1,29 @@
+//! Input helpers shared by the fuzz targets and the seed corpus generator.
+
+use lz4_flex_wasm_simd::frame::{BlockMode, BlockSize, FrameInfo};
+
+/// Frame settings picked by one selector byte, so targets can cover every combination.
+pub fn frame_info(selector: u8) -> FrameInfo {
+    let block_size = match selector & 0b11 {
+        0 => BlockSize::Auto,
+        1 => BlockSize::Max64KB,
+        2 => BlockSize::Max256KB,
+        _ => BlockSize::Max1MB,
+    };
+    let block_mode = if selector & 0b100 != 0 {
+        BlockMode::Linked
+    } else {
+        BlockMode::Independent
+    };
+    FrameInfo::new()
+        .block_size(block_size)
+        .block_mode(block_mode)
+        .block_checksums(selector & 0b1000 != 0)
+        .content_checksum(selector & 0b1_0000 != 0)
+}
+
+/// Splits the little-endian `u16` output capacity off the front of a decoder target input.
+pub fn split_capacity(data: &[u8]) -> Option<(usize, &[u8])> {
+    let (&[lo, hi], rest) = data.split_first_chunk::<2>()?;
+    Some((u16::from_le_bytes([lo, hi]) as usize, rest))
+}
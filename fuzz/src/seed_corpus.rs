@@ -0,0 +1,100 @@
+//! Writes a seed corpus for every target into `corpus/<target>/`, built from `bench-data` and a
+//! few synthetic inputs:
+//!
+//!     cargo run --release --bin seed_corpus
+//!
+//! Decoder targets get compressed blocks and frames in their input layout; the round trip and
+//! differential targets get the raw samples.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use lz4_flex_wasm_simd::block::{compress, compress_with_dict};
+use lz4_flex_wasm_simd::frame::FrameEncoder;
+use lz4_flex_wasm_simd_fuzz::frame_info;
+
+fn samples(root: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut samples = Vec::new();
+    let mut entries: Vec<PathBuf> = fs::read_dir(root.join("bench-data"))
+        .expect("bench-data")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext != "sha256"))
+        .collect();
+    entries.sort();
+    for path in entries {
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        samples.push((name, fs::read(&path).unwrap()));
+    }
+
+    samples.push(("empty".into(), Vec::new()));
+    samples.push(("run".into(), vec![b'z'; 5000]));
+    let short_period: Vec<u8> = (0..4000u32).map(|i| b"abcdefg"[i as usize % 7]).collect();
+    samples.push(("short_period".into(), short_period));
+    let noise: Vec<u8> = (0..3000u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+        .collect();
+    samples.push(("noise".into(), noise));
+    samples
+}
+
+fn write(dir: &Path, name: &str, parts: &[&[u8]]) {
+    fs::create_dir_all(dir).unwrap();
+    let mut file = fs::File::create(dir.join(name)).unwrap();
+    for part in parts {
+        file.write_all(part).unwrap();
+    }
+}
+
+fn capacity(len: usize) -> [u8; 2] {
+    (len.min(u16::MAX as usize) as u16).to_le_bytes()
+}
+
+fn main() {
+    let fuzz_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let corpus = fuzz_dir.join("corpus");
+
+    for (name, data) in samples(fuzz_dir.parent().unwrap()) {
+        // Block decoders take a u16 capacity, so longer samples are cut.
+        let data = &data[..data.len().min(u16::MAX as usize)];
+        let block = compress(data);
+        write(
+            &corpus.join("decompress_into"),
+            &name,
+            &[&capacity(data.len()), &block],
+        );
+        let short = &data[..data.len().min(255 * 64)];
+        write(
+            &corpus.join("decompress_checked"),
+            &name,
+            &[&[short.len().div_ceil(64) as u8, 0], &compress(short)],
+        );
+
+        let (dict, rest) = data.split_at(data.len() / 2);
+        write(
+            &corpus.join("decompress_with_dict"),
+            &name,
+            &[
+                &capacity(rest.len()),
+                &capacity(dict.len()),
+                dict,
+                &compress_with_dict(rest, dict),
+            ],
+        );
+
+        for selector in [0u8, 0b0_0101, 0b1_1110, 0b1_1111] {
+            let mut encoder = FrameEncoder::with_frame_info(frame_info(selector), Vec::new());
+            encoder.write_all(data).unwrap();
+            let frame = encoder.finish().unwrap();
+            write(
+                &corpus.join("frame_decoder"),
+                &format!("{name}_{selector:02x}"),
+                &[&frame],
+            );
+        }
+
+        for target in ["roundtrip", "differential"] {
+            write(&corpus.join(target), &name, &[data]);
+        }
+    }
+}
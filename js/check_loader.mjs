@@ -0,0 +1,56 @@
+// Smoke test for loader.mjs against a directory produced by scripts/build_wasm_dual.sh.
+//
+//   node js/check_loader.mjs pkg
+
+import { readFile } from "node:fs/promises";
+import { join } from "node:path";
+
+import { loadLz4, simdSupported } from "./loader.mjs";
+
+const dir = process.argv[2] ?? "pkg";
+const simd = await readFile(join(dir, "lz4_flex_wasm_simd_simd.wasm"));
+const scalar = await readFile(join(dir, "lz4_flex_wasm_simd_scalar.wasm"));
+
+function check(label, loaded, expectSimd) {
+  const { exports } = loaded;
+  if (loaded.simd !== expectSimd || exports.wasm_simd_enabled() !== (expectSimd ? 1 : 0)) {
+    throw new Error(`${label}: loaded the wrong artifact`);
+  }
+  if (exports.wasm_block_roundtrip() !== 1 || exports.wasm_hash_consistency() !== 1) {
+    throw new Error(`${label}: runtime validation failed`);
+  }
+  checkCompressDestSize(label, exports);
+  console.log(`${label}: simd=${loaded.simd} ok`);
+}
+
+// Fills a 256 byte page from a longer input through the pointer-based export.
+function checkCompressDestSize(label, exports) {
+  const input = new Uint8Array(4096);
+  for (let i = 0, x = 1; i < input.length; i++) {
+    x = (x * 1103515245 + 12345) >>> 0;
+    input[i] = i % 3 === 0 ? x >>> 24 : 0x61;
+  }
+  const capacity = 256;
+  const src = exports.wasm_alloc(input.length);
+  const dst = exports.wasm_alloc(capacity);
+  new Uint8Array(exports.memory.buffer, src, input.length).set(input);
+  const packed = exports.wasm_compress_dest_size(src, input.length, dst, capacity);
+  const consumed = Number(packed >> 32n);
+  const written = Number(packed & 0xffffffffn);
+  exports.wasm_dealloc(src, input.length);
+  exports.wasm_dealloc(dst, capacity);
+  if (consumed === 0 || consumed >= input.length || written > capacity || written < capacity - 2) {
+    throw new Error(`${label}: compress_dest_size returned ${consumed}/${written}`);
+  }
+}
+
+check("auto", await loadLz4({ simd, scalar }), simdSupported());
+check("forced scalar", await loadLz4({ simd, scalar, forceScalar: true }), false);
+check("scalar only", await loadLz4({ scalar }), false);
+
+// Simulate an engine without SIMD128: a fresh loader instance sees the probe rejected.
+const validate = WebAssembly.validate;
+WebAssembly.validate = () => false;
+const noSimd = await import("./loader.mjs?no-simd");
+check("no simd engine", await noSimd.loadLz4({ simd, scalar }), false);
+WebAssembly.validate = validate;
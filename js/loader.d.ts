@@ -0,0 +1,25 @@
+export type WasmSource =
+  | string
+  | URL
+  | Request
+  | Response
+  | ArrayBuffer
+  | ArrayBufferView
+  | WebAssembly.Module;
+
+export interface LoadOptions {
+  simd?: WasmSource;
+  scalar?: WasmSource;
+  imports?: WebAssembly.Imports;
+  forceScalar?: boolean;
+}
+
+export interface LoadedLz4 {
+  instance: WebAssembly.Instance;
+  module: WebAssembly.Module;
+  exports: WebAssembly.Exports;
+  simd: boolean;
+}
+
+export function simdSupported(): boolean;
+export function loadLz4(options: LoadOptions): Promise<LoadedLz4>;
@@ -0,0 +1,79 @@
+// Picks the SIMD or scalar build of lz4_flex_wasm_simd at runtime.
+//
+// A module that contains any SIMD instruction fails validation as a whole on engines
+// without SIMD128, so one binary cannot carry both paths. Instead,
+// `scripts/build_wasm_dual.sh` builds two artifacts and this loader validates a tiny SIMD
+// probe to decide which one to instantiate.
+
+// (module (func (result v128) i32.const 0 i8x16.splat i8x16.popcnt))
+const SIMD_PROBE = new Uint8Array([
+  0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7b,
+  0x03, 0x02, 0x01, 0x00, 0x0a, 0x0a, 0x01, 0x08, 0x00, 0x41, 0x00, 0xfd, 0x0f, 0xfd, 0x62,
+  0x0b,
+]);
+
+let simdSupportedCache;
+
+/** Returns true if the engine accepts wasm SIMD128 instructions. */
+export function simdSupported() {
+  if (simdSupportedCache === undefined) {
+    try {
+      simdSupportedCache = WebAssembly.validate(SIMD_PROBE);
+    } catch {
+      simdSupportedCache = false;
+    }
+  }
+  return simdSupportedCache;
+}
+
+async function instantiate(source, imports) {
+  if (source instanceof WebAssembly.Module) {
+    return { module: source, instance: await WebAssembly.instantiate(source, imports) };
+  }
+  if (source instanceof ArrayBuffer || ArrayBuffer.isView(source)) {
+    return WebAssembly.instantiate(source, imports);
+  }
+
+  const isResponse = typeof Response !== "undefined" && source instanceof Response;
+  const response = isResponse ? source : await fetch(source);
+  if (!response.ok) {
+    throw new Error(`failed to fetch ${response.url}: ${response.status}`);
+  }
+  if (typeof WebAssembly.instantiateStreaming === "function") {
+    try {
+      return await WebAssembly.instantiateStreaming(response.clone(), imports);
+    } catch (err) {
+      // Servers that do not send `application/wasm` make streaming compilation fail.
+      if (!(err instanceof TypeError)) {
+        throw err;
+      }
+    }
+  }
+  return WebAssembly.instantiate(await response.arrayBuffer(), imports);
+}
+
+/**
+ * Instantiates the SIMD build when the engine supports it and the scalar build otherwise.
+ *
+ * `simd` and `scalar` may be URLs, `Response`s, byte buffers or compiled
+ * `WebAssembly.Module`s. Set `forceScalar` to skip the probe.
+ *
+ * Resolves to `{ instance, module, exports, simd }`, where `simd` says which build was
+ * loaded.
+ */
+export async function loadLz4({ simd, scalar, imports = {}, forceScalar = false }) {
+  const useSimd = !forceScalar && simd !== undefined && simdSupported();
+  const source = useSimd ? simd : scalar;
+  if (source === undefined) {
+    throw new Error("loadLz4: no scalar artifact given and SIMD is unavailable");
+  }
+
+  const { module, instance } = await instantiate(source, imports);
+  const probe = instance.exports.wasm_simd_enabled;
+  if (typeof probe === "function" && (probe() === 1) !== useSimd) {
+    throw new Error(
+      `loadLz4: expected the ${useSimd ? "simd" : "scalar"} build but the module reports otherwise`,
+    );
+  }
+  return { instance, module, exports: instance.exports, simd: useSimd };
+}
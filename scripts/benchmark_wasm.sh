@@ -262,6 +262,15 @@ while IFS='|' read -r impl_id mode wasm_path display; do
     echo "runtime validation failed for ${display}/${mode}" >&2
     exit 1
   fi
+  if [[ "$impl_id" == "simdcrate" ]]; then
+    simd_flag="$(invoke "$wasm_path" wasm_simd_enabled || true)"
+    expected_flag="0"
+    [[ "$mode" == "simd" ]] && expected_flag="1"
+    if [[ "$simd_flag" != "$expected_flag" ]]; then
+      echo "wasm_simd_enabled=${simd_flag} for ${display}/${mode}" >&2
+      exit 1
+    fi
+  fi
 
   c_line="$(run_series "$wasm_path" wasm_compress_repeated "${impl_id}-${mode}-compress" "$INNER_ITERS" "$PAYLOAD_BYTES")"
   c_med="$(echo "$c_line" | sed -E 's/.*median=([0-9]+).*/\1/')"
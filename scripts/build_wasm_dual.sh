@@ -0,0 +1,42 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+# Builds scalar and SIMD128 variants of the crate as a single package directory, together
+# with js/loader.mjs, which picks one of them at runtime.
+
+TARGET="${TARGET:-wasm32-unknown-unknown}"
+FEATURES="${FEATURES:-frame,block,wasm-exports}"
+OUT_DIR="${OUT_DIR:-pkg}"
+
+ROOT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")/.." && pwd)"
+CRATE_NAME="lz4_flex_wasm_simd"
+
+build_variant() {
+  local mode="$1"
+  local rustflags="$2"
+  local target_dir="${ROOT_DIR}/target-${mode}"
+
+  echo "== building ${mode} (${TARGET}, features=${FEATURES}) =="
+  CARGO_TARGET_DIR="$target_dir" RUSTFLAGS="$rustflags" \
+    cargo rustc --manifest-path "${ROOT_DIR}/Cargo.toml" --release --target "$TARGET" \
+    --no-default-features --features "$FEATURES" --crate-type=cdylib
+
+  local wasm_path="${target_dir}/${TARGET}/release/${CRATE_NAME}.wasm"
+  if [[ ! -f "$wasm_path" ]]; then
+    echo "build missing artifact: ${wasm_path}" >&2
+    exit 1
+  fi
+  cp "$wasm_path" "${OUT_DIR}/${CRATE_NAME}_${mode}.wasm"
+  echo "${mode} bytes: $(wc -c < "${OUT_DIR}/${CRATE_NAME}_${mode}.wasm")"
+}
+
+mkdir -p "$OUT_DIR"
+build_variant "scalar" ""
+build_variant "simd" "-C target-feature=+simd128"
+cp "${ROOT_DIR}/js/loader.mjs" "${ROOT_DIR}/js/loader.d.ts" "$OUT_DIR/"
+
+if command -v node >/dev/null 2>&1; then
+  node "${ROOT_DIR}/js/check_loader.mjs" "$OUT_DIR"
+else
+  echo "node not found; loader check skipped."
+fi
@@ -0,0 +1,136 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+# Regenerates tests/conformance: LZ4 frames written by the reference C `lz4` CLI, plus
+# MANIFEST.sha256 with the sha256 of each frame's decompressed content as decoded by `lz4 -d`.
+# Only needed when adding cases; the tests read the checked-in files.
+
+ROOT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")/.." && pwd)"
+OUT_DIR="${OUT_DIR:-${ROOT_DIR}/tests/conformance}"
+LZ4="${LZ4:-lz4}"
+WORK_DIR="$(mktemp -d)"
+trap 'rm -rf "$WORK_DIR"' EXIT
+
+sha256_file() {
+  local path="$1"
+  if command -v sha256sum >/dev/null 2>&1; then
+    sha256sum "$path" | awk '{print $1}'
+    return
+  fi
+  shasum -a 256 "$path" | awk '{print $1}'
+}
+
+TEXT="${ROOT_DIR}/bench-data/text_50kb.txt"
+JSON="${ROOT_DIR}/bench-data/json_50kb.json"
+# 1.1 MiB of mostly runs with short text pieces, so that 64 KiB to 1 MiB blocks all split it
+# into several blocks while the frames stay small.
+BIG="${WORK_DIR}/big.bin"
+python3 - "$TEXT" "$BIG" <<'EOF'
+import sys
+text = open(sys.argv[1], "rb").read()
+out = bytearray()
+i = 0
+while len(out) < 1100 * 1024:
+    start = (i * 523) % (len(text) - 64)
+    out += text[start:start + 64]
+    out += bytes([i % 251]) * 4032
+    i += 1
+open(sys.argv[2], "wb").write(out)
+EOF
+EMPTY="${WORK_DIR}/empty.bin"
+: > "$EMPTY"
+DICT="${WORK_DIR}/dict.bin"
+head -c 16384 "$JSON" > "$DICT"
+
+mkdir -p "$OUT_DIR"
+rm -f "$OUT_DIR"/*.lz4
+
+frame() {
+  local name="$1" input="$2"
+  shift 2
+  "$LZ4" -q -c "$@" "$input" > "${OUT_DIR}/${name}.lz4"
+}
+
+frame text-b4-indep "$TEXT" -B4
+frame text-b4-blockcrc-nocrc "$TEXT" -B4 -BX --no-frame-crc
+# 16 KiB blocks in a frame that declares 64 KiB, so the text spans several linked blocks.
+frame text-16k-linked "$TEXT" -B16384 -BD
+frame text-16k-linked-hc "$TEXT" -B16384 -BD -9
+frame json-b5-size "$JSON" -B5 --content-size
+frame json-b4-linked-blockcrc-size "$JSON" -B4 -BD -BX --content-size
+frame big-b4-linked "$BIG" -B4 -BD
+frame big-b5-indep "$BIG" -B5
+frame big-b6-linked-blockcrc "$BIG" -B6 -BD -BX
+frame big-b7-size-nocrc "$BIG" -B7 --content-size --no-frame-crc
+frame empty "$EMPTY"
+frame text-legacy "$TEXT" -l
+frame big-legacy "$BIG" -l
+
+# Two frames back to back.
+cat "${OUT_DIR}/text-b4-indep.lz4" "${OUT_DIR}/json-b5-size.lz4" > "${OUT_DIR}/concat.lz4"
+
+# The CLI never writes a dictionary ID, so one is patched into a dictionary-compressed frame.
+"$LZ4" -q -c -B4 -D "$DICT" "$JSON" > "${WORK_DIR}/dict.lz4"
+python3 - "${WORK_DIR}/dict.lz4" "${OUT_DIR}/dictid.lz4" <<'EOF'
+import struct, sys
+
+def xxh32(data, seed=0):
+    p1, p2, p3, p4, p5 = 2654435761, 2246822519, 3266489917, 668265263, 374761393
+    mask = 0xFFFFFFFF
+    rotl = lambda x, r: ((x << r) | (x >> (32 - r))) & mask
+    rnd = lambda acc, lane: (rotl((acc + lane * p2) & mask, 13) * p1) & mask
+    i, n = 0, len(data)
+    if n >= 16:
+        v = [(seed + p1 + p2) & mask, (seed + p2) & mask, seed, (seed - p1) & mask]
+        while i + 16 <= n:
+            for j in range(4):
+                v[j] = rnd(v[j], struct.unpack_from("<I", data, i + 4 * j)[0])
+            i += 16
+        h = (rotl(v[0], 1) + rotl(v[1], 7) + rotl(v[2], 12) + rotl(v[3], 18)) & mask
+    else:
+        h = (seed + p5) & mask
+    h = (h + n) & mask
+    while i + 4 <= n:
+        h = (rotl((h + struct.unpack_from("<I", data, i)[0] * p3) & mask, 17) * p4) & mask
+        i += 4
+    while i < n:
+        h = (rotl((h + data[i] * p5) & mask, 11) * p1) & mask
+        i += 1
+    h ^= h >> 15
+    h = (h * p2) & mask
+    h ^= h >> 13
+    h = (h * p3) & mask
+    return h ^ (h >> 16)
+
+frame = open(sys.argv[1], "rb").read()
+flg, bd = frame[4], frame[5]
+assert flg & 0x09 == 0, "expected no content size and no dictionary ID"
+descriptor = bytes([flg | 0x01, bd]) + struct.pack("<I", 0x5EED1234)
+hc = (xxh32(descriptor) >> 8) & 0xFF
+open(sys.argv[2], "wb").write(frame[:4] + descriptor + bytes([hc]) + frame[7:])
+EOF
+"$LZ4" -q -d -c -D "$DICT" "${OUT_DIR}/dictid.lz4" > /dev/null
+
+# A skippable frame (magic 0x184D2A5A) in front of a regular one.
+python3 - "${OUT_DIR}/text-16k-linked.lz4" "${OUT_DIR}/skippable.lz4" <<'EOF'
+import struct, sys
+payload = b"skippable frame payload"
+frame = open(sys.argv[1], "rb").read()
+out = struct.pack("<II", 0x184D2A5A, len(payload)) + payload + frame
+open(sys.argv[2], "wb").write(out)
+EOF
+
+{
+  echo "# sha256 of the decompressed content of each frame, as decoded by: $("$LZ4" -V 2>&1 | head -n1 | tr -d '*' | sed 's/^ *//;s/ *$//')"
+  for path in "$OUT_DIR"/*.lz4; do
+    name="$(basename "$path")"
+    if [[ "$name" == dictid.lz4 ]]; then
+      "$LZ4" -q -d -c -D "$DICT" "$path" > "${WORK_DIR}/decoded"
+    else
+      "$LZ4" -q -d -c "$path" > "${WORK_DIR}/decoded"
+    fi
+    echo "$(sha256_file "${WORK_DIR}/decoded")  ${name}"
+  done
+} > "${OUT_DIR}/MANIFEST.sha256"
+
+echo "wrote $(ls "$OUT_DIR"/*.lz4 | wc -l | tr -d ' ') frames to $OUT_DIR"
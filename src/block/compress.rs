@@ -7,13 +7,18 @@
 
 use crate::block::hashtable::HashTable;
 use crate::block::END_OFFSET;
+use crate::block::LAST_LITERALS;
 use crate::block::LZ4_MIN_LENGTH;
 use crate::block::MAX_DISTANCE;
 use crate::block::MFLIMIT;
 use crate::block::MINMATCH;
-#[cfg(not(feature = "safe-encode"))]
+#[cfg(all(
+    any(feature = "block", feature = "wasm-exports", test),
+    not(feature = "safe-encode")
+))]
 use crate::sink::PtrSink;
 use crate::sink::Sink;
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
 use crate::sink::SliceSink;
 #[allow(unused_imports)]
 use alloc::vec;
@@ -21,13 +26,95 @@ use alloc::vec;
 #[allow(unused_imports)]
 use alloc::vec::Vec;
 
-use super::hashtable::HashTable4K;
-use super::hashtable::HashTable4KU16;
-use super::{CompressError, WINDOW_SIZE};
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+use super::hashtable::{HashTableU16, HashTableU16Stack, HashTableU32};
+#[cfg(any(feature = "block", test))]
+use super::stats::CompressStats;
+use super::stats::StatsRecorder;
+use super::CompressError;
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+use super::WINDOW_SIZE;
+#[cfg(any(
+    feature = "block",
+    all(feature = "wasm-exports", not(feature = "safe-encode")),
+    test
+))]
+use core::mem::MaybeUninit;
 
 /// Increase step size after 1<<INCREASE_STEPSIZE_BITSHIFT non matches
 const INCREASE_STEPSIZE_BITSHIFT: usize = 5;
 
+/// Runs of a single byte at least this long are matched at offset 1 by the run fast path.
+#[cfg(any(target_arch = "x86_64", feature = "compress-prof"))]
+const MIN_RUN_LENGTH: usize = 32;
+
+/// Counters of the block compressor's run fast path, see [`read_compress_profile`].
+///
+/// Only x86_64 has the run fast path. On other targets the counters count the runs it would have
+/// matched, while the hash table search still compresses them as before.
+#[cfg(feature = "compress-prof")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CompressProfileSnapshot {
+    /// Offset 1 matches written by the run fast path.
+    pub run_matches: u64,
+    /// Bytes covered by those matches.
+    pub run_bytes: u64,
+}
+
+#[cfg(feature = "compress-prof")]
+mod profile {
+    use super::CompressProfileSnapshot;
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    pub(super) static RUN_MATCHES: AtomicU64 = AtomicU64::new(0);
+    pub(super) static RUN_BYTES: AtomicU64 = AtomicU64::new(0);
+
+    #[inline]
+    pub(super) fn inc(counter: &AtomicU64, n: u64) {
+        counter.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub(super) fn reset() {
+        RUN_MATCHES.store(0, Ordering::Relaxed);
+        RUN_BYTES.store(0, Ordering::Relaxed);
+    }
+
+    pub(super) fn snapshot() -> CompressProfileSnapshot {
+        CompressProfileSnapshot {
+            run_matches: RUN_MATCHES.load(Ordering::Relaxed),
+            run_bytes: RUN_BYTES.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(feature = "compress-prof")]
+macro_rules! prof_inc {
+    ($counter:ident, $n:expr) => {
+        profile::inc(&profile::$counter, $n as u64)
+    };
+}
+
+#[cfg(all(not(feature = "compress-prof"), target_arch = "x86_64"))]
+macro_rules! prof_inc {
+    ($counter:ident, $n:expr) => {
+        let _ = &$n;
+    };
+}
+
+/// Resets the run fast path counters of the whole process to zero.
+#[cfg(feature = "compress-prof")]
+pub fn reset_compress_profile() {
+    profile::reset();
+}
+
+/// Reads the run fast path counters of the whole process, summed over all threads since the
+/// start or the last [`reset_compress_profile`].
+#[cfg(feature = "compress-prof")]
+pub fn read_compress_profile() -> CompressProfileSnapshot {
+    profile::snapshot()
+}
+
 /// Read a 4-byte "batch" from some position.
 ///
 /// This will read a native-endian 4-byte integer from some position.
@@ -198,6 +285,26 @@ fn count_same_bytes(input: &[u8], cur: &mut usize, source: &[u8], candidate: usi
     *cur - start
 }
 
+/// Counts the number of same bytes in two byte streams using SSE2/AVX2 or NEON.
+/// See [`crate::simd::count_same_bytes_simd`] for the backend selection.
+#[inline]
+#[cfg(all(
+    any(target_arch = "x86_64", target_arch = "aarch64"),
+    not(feature = "safe-encode")
+))]
+fn count_same_bytes(input: &[u8], cur: &mut usize, source: &[u8], candidate: usize) -> usize {
+    let max_input_match = input.len().saturating_sub(*cur + END_OFFSET);
+    let max_candidate_match = source.len() - candidate;
+    let len = max_input_match.min(max_candidate_match);
+
+    let num = crate::simd::count_same_bytes_simd(
+        &input[*cur..*cur + len],
+        &source[candidate..candidate + len],
+    );
+    *cur += num;
+    num
+}
+
 /// Counts the number of same bytes in two byte streams.
 /// `input` is the complete input
 /// `cur` is the current position in the input. it will be incremented by the number of matched
@@ -208,6 +315,7 @@ fn count_same_bytes(input: &[u8], cur: &mut usize, source: &[u8], candidate: usi
 #[inline]
 #[cfg(all(
     not(all(target_arch = "wasm32", target_feature = "simd128")),
+    not(any(target_arch = "x86_64", target_arch = "aarch64")),
     not(feature = "safe-encode")
 ))]
 fn count_same_bytes(input: &[u8], cur: &mut usize, source: &[u8], candidate: usize) -> usize {
@@ -272,6 +380,90 @@ fn count_same_bytes(input: &[u8], cur: &mut usize, source: &[u8], candidate: usi
     *cur - start
 }
 
+/// Counts the bytes equal to `byte` from `start` on.
+///
+/// The function ignores the last END_OFFSET bytes in input as those should be literals.
+#[cfg(any(target_arch = "x86_64", feature = "compress-prof"))]
+#[inline]
+fn count_run(input: &[u8], start: usize, byte: u8) -> usize {
+    let run = &input[start..input.len() - END_OFFSET];
+    #[cfg(all(target_arch = "x86_64", not(feature = "safe-encode")))]
+    {
+        crate::simd::count_run_simd(run, byte)
+    }
+    #[cfg(not(all(target_arch = "x86_64", not(feature = "safe-encode"))))]
+    {
+        let pattern = u64::from_ne_bytes([byte; 8]);
+        let mut chunks = run.chunks_exact(8);
+        let mut num = 0;
+        for chunk in &mut chunks {
+            let diff = u64::from_ne_bytes(chunk.try_into().unwrap()) ^ pattern;
+            if diff != 0 {
+                return num + (diff.to_le().trailing_zeros() / 8) as usize;
+            }
+            num += 8;
+        }
+        num + chunks
+            .remainder()
+            .iter()
+            .take_while(|&&b| b == byte)
+            .count()
+    }
+}
+
+/// Writes a sequence without literals that matches the run at `cur` at offset 1, if the run
+/// continues the byte before it for at least `MIN_RUN_LENGTH` bytes. Returns the position after
+/// the run, or `cur` if there is none.
+///
+/// Only x86_64 writes the sequence. In wasm the hash table search already finds the rest of the
+/// run at its first position and extends the match 16 bytes at a time, and the run fast path made
+/// the bitmap and string-page benchmarks 2% to 6% slower. Other targets only count the run with
+/// `compress-prof` and return `cur`.
+#[cfg(any(target_arch = "x86_64", feature = "compress-prof"))]
+#[inline(never)]
+fn push_run<T: HashTable, const FILL_OUTPUT: bool>(
+    output: &mut impl Sink,
+    input: &[u8],
+    cur: usize,
+    dict: &mut T,
+    input_stream_offset: usize,
+    stats: &mut impl StatsRecorder,
+) -> usize {
+    if cur + MIN_RUN_LENGTH > input.len() - END_OFFSET {
+        return cur;
+    }
+    let byte = input[cur - 1];
+    // Probe both ends of the shortest run before counting.
+    let splat = u32::from_ne_bytes([byte; 4]);
+    if get_batch(input, cur) != splat || get_batch(input, cur + MIN_RUN_LENGTH - 4) != splat {
+        return cur;
+    }
+    let run_len = count_run(input, cur, byte);
+    if run_len < MIN_RUN_LENGTH
+        || FILL_OUTPUT && output.pos() + 3 + run_len / 255 + MFLIMIT > output.capacity()
+    {
+        return cur;
+    }
+
+    prof_inc!(RUN_MATCHES, 1);
+    prof_inc!(RUN_BYTES, run_len);
+    if !cfg!(target_arch = "x86_64") {
+        return cur;
+    }
+    stats.sequence(0, run_len, 1);
+    let match_len = run_len - MINMATCH;
+    push_byte(output, token_from_literal_and_match_length(0, match_len));
+    push_u16(output, 1);
+    if match_len >= 0xF {
+        write_integer(output, match_len - 0xF);
+    }
+
+    let end = cur + run_len;
+    let hash = T::get_hash_at(input, end - 2);
+    dict.put_at(hash, end - 2 + input_stream_offset);
+    end
+}
+
 /// Write an integer to the output.
 ///
 /// Each additional byte then represent a value from 0 to 255, which is added to the previous value
@@ -290,9 +482,26 @@ pub(super) fn write_integer(output: &mut impl Sink, mut n: usize) {
 }
 
 /// Handle the last bytes from the input as literals
+///
+/// With `FILL_OUTPUT`, only as many literals as fit into `output` are written. Returns the input
+/// position after the last literal written.
 #[cold]
-fn handle_last_literals(output: &mut impl Sink, input: &[u8], start: usize) {
-    let lit_len = input.len() - start;
+fn handle_last_literals<const FILL_OUTPUT: bool>(
+    output: &mut impl Sink,
+    input: &[u8],
+    start: usize,
+    stats: &mut impl StatsRecorder,
+) -> usize {
+    let mut lit_len = input.len() - start;
+    if FILL_OUTPUT {
+        let room = output.capacity() - output.pos();
+        debug_assert!(room > 0);
+        if 1 + (lit_len + 240) / 255 + lit_len > room {
+            // Take the token and the length bytes out of the room that is left.
+            lit_len = room - 1;
+            lit_len -= (lit_len + 256 - 0xF) / 256;
+        }
+    }
 
     let token = token_from_literal(lit_len);
     push_byte(output, token);
@@ -300,7 +509,9 @@ fn handle_last_literals(output: &mut impl Sink, input: &[u8], start: usize) {
         write_integer(output, lit_len - 0xF);
     }
     // Now, write the actual literals.
-    output.extend_from_slice(&input[start..]);
+    output.extend_from_slice(&input[start..start + lit_len]);
+    stats.last_literals(lit_len);
+    start + lit_len
 }
 
 /// Moves the cursors back as long as the bytes match, to find additional bytes in a duplicate
@@ -360,6 +571,8 @@ fn backtrack_match(
 /// Every four bytes are hashed, and in the resulting slot their position in the input buffer
 /// is placed in the dict. This way we can easily look up a candidate to back references.
 ///
+/// `stats` receives what the compressor does, `()` ignores it.
+///
 /// Returns the number of bytes written (compressed) into `output`.
 ///
 /// # Const parameters
@@ -369,17 +582,58 @@ fn backtrack_match(
 /// A similar const argument could be used to disable the Prefix mode (eg. USE_PREFIX),
 /// which would impose `input_pos == 0 && input_stream_offset == 0`. Experiments didn't
 /// show significant improvement though.
+#[inline]
+pub(crate) fn compress_internal<T: HashTable, const USE_DICT: bool, S: Sink, R: StatsRecorder>(
+    input: &[u8],
+    input_pos: usize,
+    output: &mut S,
+    dict: &mut T,
+    ext_dict: &[u8],
+    input_stream_offset: usize,
+    stats: &mut R,
+) -> Result<usize, CompressError> {
+    compress_generic::<T, USE_DICT, false, S, R>(
+        input,
+        input_pos,
+        output,
+        dict,
+        ext_dict,
+        input_stream_offset,
+        stats,
+    )
+    .map(|(_, written)| {
+        stats.block(input.len() - input_pos, written);
+        written
+    })
+}
+
+/// The compression loop behind [`compress_internal`].
+///
+/// Returns the input position compression stopped at and the number of bytes written.
+///
+/// `FILL_OUTPUT`: Instead of requiring `get_maximum_output_size` bytes of room, compress only as
+/// much of the input as fits into the room left in `output`, like `LZ4_compress_destSize`. A
+/// sequence is only emitted if the token, literals, offset and enough room for the last literals
+/// (so the match still ends `MFLIMIT` bytes before the end of the block) fit. Otherwise, or once a
+/// match had to be shortened to fit, the block ends with as many literals as there is room for.
 // Intentionally avoid inlining.
 // Empirical tests revealed it to be rarely better but often significantly detrimental.
 #[inline(never)]
-pub(crate) fn compress_internal<T: HashTable, const USE_DICT: bool, S: Sink>(
+fn compress_generic<
+    T: HashTable,
+    const USE_DICT: bool,
+    const FILL_OUTPUT: bool,
+    S: Sink,
+    R: StatsRecorder,
+>(
     input: &[u8],
     input_pos: usize,
     output: &mut S,
     dict: &mut T,
     ext_dict: &[u8],
     input_stream_offset: usize,
-) -> Result<usize, CompressError> {
+    stats: &mut R,
+) -> Result<(usize, usize), CompressError> {
     assert!(input_pos <= input.len());
     if USE_DICT {
         assert!(ext_dict.len() <= super::WINDOW_SIZE);
@@ -392,14 +646,16 @@ pub(crate) fn compress_internal<T: HashTable, const USE_DICT: bool, S: Sink>(
     } else {
         assert!(ext_dict.is_empty());
     }
-    if output.capacity() - output.pos() < get_maximum_output_size(input.len() - input_pos) {
+    if !FILL_OUTPUT
+        && output.capacity() - output.pos() < get_maximum_output_size(input.len() - input_pos)
+    {
         return Err(CompressError::OutputTooSmall);
     }
 
     let output_start_pos = output.pos();
     if input.len() - input_pos < LZ4_MIN_LENGTH {
-        handle_last_literals(output, input, input_pos);
-        return Ok(output.pos() - output_start_pos);
+        let end = handle_last_literals::<FILL_OUTPUT>(output, input, input_pos, stats);
+        return Ok((end, output.pos() - output_start_pos));
     }
 
     let ext_dict_stream_offset = input_stream_offset - ext_dict.len();
@@ -430,14 +686,17 @@ pub(crate) fn compress_internal<T: HashTable, const USE_DICT: bool, S: Sink>(
         loop {
             step_size = non_match_count >> INCREASE_STEPSIZE_BITSHIFT;
             non_match_count += 1;
+            if non_match_count & ((1 << INCREASE_STEPSIZE_BITSHIFT) - 1) == 0 {
+                stats.step_escalation();
+            }
 
             cur = next_cur;
             next_cur += step_size;
 
             // Same as cur + MFLIMIT > input.len()
             if cur > end_pos_check {
-                handle_last_literals(output, input, literal_start);
-                return Ok(output.pos() - output_start_pos);
+                let end = handle_last_literals::<FILL_OUTPUT>(output, input, literal_start, stats);
+                return Ok((end, output.pos() - output_start_pos));
             }
             // Find a candidate in the dictionary with the hash of the current four bytes.
             // Unchecked is safe as long as the values from the hash function don't exceed the size
@@ -458,6 +717,7 @@ pub(crate) fn compress_internal<T: HashTable, const USE_DICT: bool, S: Sink>(
             // - We can address up to 16-bit offset, hence we are only able to address the candidate
             //   if its offset is less than or equals to 0xFFFF.
             if input_stream_offset + cur - candidate > MAX_DISTANCE {
+                stats.hash_miss();
                 continue;
             }
 
@@ -482,6 +742,7 @@ pub(crate) fn compress_internal<T: HashTable, const USE_DICT: bool, S: Sink>(
                 // the matches tables, only increasing input_stream_offset.
                 // Sanity check
                 debug_assert!(input_pos == 0, "Lost history in prefix mode");
+                stats.hash_miss();
                 continue;
             }
             // [Bounds Check]: Candidate is coming from the Hashmap. It can't be out of bounds, but
@@ -491,8 +752,10 @@ pub(crate) fn compress_internal<T: HashTable, const USE_DICT: bool, S: Sink>(
             let curr_bytes: u32 = get_batch(input, cur);
 
             if cand_bytes == curr_bytes {
+                stats.hash_hit();
                 break;
             }
+            stats.hash_collision();
         }
 
         // Extend the match backwards if we can
@@ -507,16 +770,38 @@ pub(crate) fn compress_internal<T: HashTable, const USE_DICT: bool, S: Sink>(
         // The length (in bytes) of the literals section.
         let lit_len = cur - literal_start;
 
+        // The token, literals and offset of this sequence, plus at least `MFLIMIT - MINMATCH`
+        // last literals after the shortest match.
+        let sequence_len = 1 + (lit_len + 240) / 255 + lit_len + 2;
+        if FILL_OUTPUT && output.pos() + sequence_len + 1 + MFLIMIT - MINMATCH > output.capacity() {
+            let end = handle_last_literals::<true>(output, input, literal_start, stats);
+            return Ok((end, output.pos() - output_start_pos));
+        }
+
         // Generate the higher half of the token.
         cur += MINMATCH;
         candidate += MINMATCH;
-        let duplicate_length = count_same_bytes(input, &mut cur, candidate_source, candidate);
+        let mut duplicate_length = count_same_bytes(input, &mut cur, candidate_source, candidate);
+
+        // The match length bytes must leave room for a token and `LAST_LITERALS` literals.
+        let mut shortened = false;
+        if FILL_OUTPUT {
+            let room = output.capacity() - output.pos() - sequence_len;
+            if 1 + LAST_LITERALS + (duplicate_length + 240) / 255 > room {
+                // The longest match length whose length bytes fill the room up to that point.
+                let fitting_length = 0xF - 1 + (room - 1 - LAST_LITERALS) * 255;
+                cur -= duplicate_length - fitting_length;
+                duplicate_length = fitting_length;
+                shortened = true;
+            }
+        }
 
         // Note: The `- 2` offset was copied from the reference implementation, it could be
         // arbitrary.
         let hash = T::get_hash_at(input, cur - 2);
         dict.put_at(hash, cur - 2 + input_stream_offset);
 
+        stats.sequence(lit_len, duplicate_length + MINMATCH, offset);
         let token = token_from_literal_and_match_length(lit_len, duplicate_length);
 
         // Push the token to the output stream.
@@ -542,6 +827,20 @@ pub(crate) fn compress_internal<T: HashTable, const USE_DICT: bool, S: Sink>(
             write_integer(output, duplicate_length - 0xF);
         }
         literal_start = cur;
+
+        if FILL_OUTPUT && shortened {
+            let end = handle_last_literals::<true>(output, input, literal_start, stats);
+            return Ok((end, output.pos() - output_start_pos));
+        }
+
+        // Run fast path: a long match that ended inside a run of one byte, because its source did
+        // not continue the run, is followed by an offset 1 match over the rest of the run. Short
+        // matches are not checked, they are common in data without long runs.
+        #[cfg(any(target_arch = "x86_64", feature = "compress-prof"))]
+        if duplicate_length >= 0xF && input[cur - 1] == input[cur] {
+            cur = push_run::<T, FILL_OUTPUT>(output, input, cur, dict, input_stream_offset, stats);
+            literal_start = cur;
+        }
     }
 }
 
@@ -594,6 +893,9 @@ fn copy_literals_wild(output: &mut impl Sink, input: &[u8], input_start: usize,
         match len {
             0..=8 => core::ptr::copy_nonoverlapping(start_ptr, output.pos_mut_ptr(), 8),
             9..=16 => core::ptr::copy_nonoverlapping(start_ptr, output.pos_mut_ptr(), 16),
+            // Two overlapping 16-byte SIMD vectors for 17 to 32 bytes measured the same as this
+            // within noise in `wasm_compress_repeated_fixture`, and a 16-byte loop for longer
+            // literals was 3-4% slower than `memory.copy`.
             17..=24 => core::ptr::copy_nonoverlapping(start_ptr, output.pos_mut_ptr(), 24),
             _ => core::ptr::copy_nonoverlapping(start_ptr, output.pos_mut_ptr(), len),
         }
@@ -608,23 +910,69 @@ fn copy_literals_wild(output: &mut impl Sink, input: &[u8], input_start: usize,
 ///
 /// Returns the number of bytes written (compressed) into `output`.
 #[inline]
-pub(crate) fn compress_into_sink_with_dict<const USE_DICT: bool>(
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+pub(crate) fn compress_into_sink_with_dict<const MEMORY_USAGE: u32, const USE_DICT: bool>(
     input: &[u8],
     output: &mut impl Sink,
     mut dict_data: &[u8],
+    stats: &mut impl StatsRecorder,
 ) -> Result<usize, CompressError> {
-    if dict_data.len() + input.len() < u16::MAX as usize {
-        let mut dict = HashTable4KU16::new();
+    // Small inputs use a table on the stack with at least one entry per input byte, which is
+    // much cheaper to set up than the zeroed heap allocation. It takes the same upper hash bits as
+    // a `HashTableU16` of its length and is never longer than the `MEMORY_USAGE` table, so it only
+    // finds fewer matches when it is shorter than that table.
+    let len = dict_data.len() + input.len();
+    if len <= SMALL_INPUT_LIMIT {
+        let table_len = len
+            .next_power_of_two()
+            .clamp(SMALL_TABLE_MIN_LEN, HashTableU16::<MEMORY_USAGE>::LEN);
+        match table_len {
+            256 => compress_small::<256, USE_DICT>(input, output, dict_data, stats),
+            512 => compress_small::<512, USE_DICT>(input, output, dict_data, stats),
+            _ => compress_small::<SMALL_INPUT_LIMIT, USE_DICT>(input, output, dict_data, stats),
+        }
+    } else if dict_data.len() + input.len() < u16::MAX as usize {
+        let mut dict = HashTableU16::<MEMORY_USAGE>::new();
         init_dict(&mut dict, &mut dict_data);
-        compress_internal::<_, USE_DICT, _>(input, 0, output, &mut dict, dict_data, dict_data.len())
+        let dict_len = dict_data.len();
+        compress_internal::<_, USE_DICT, _, _>(
+            input, 0, output, &mut dict, dict_data, dict_len, stats,
+        )
     } else {
-        let mut dict = HashTable4K::new();
+        let mut dict = HashTableU32::<MEMORY_USAGE>::new();
         init_dict(&mut dict, &mut dict_data);
-        compress_internal::<_, USE_DICT, _>(input, 0, output, &mut dict, dict_data, dict_data.len())
+        let dict_len = dict_data.len();
+        compress_internal::<_, USE_DICT, _, _>(
+            input, 0, output, &mut dict, dict_data, dict_len, stats,
+        )
     }
 }
 
+/// Inputs up to this length, including the dictionary, use a hash table on the stack.
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+const SMALL_INPUT_LIMIT: usize = 1024;
+
+/// Shortest stack table, the length of the smallest `MEMORY_USAGE` table.
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+const SMALL_TABLE_MIN_LEN: usize = 256;
+
+/// Compresses with a table of `LEN` entries on the stack (512 bytes to 2KB).
+#[inline(never)]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+fn compress_small<const LEN: usize, const USE_DICT: bool>(
+    input: &[u8],
+    output: &mut impl Sink,
+    mut dict_data: &[u8],
+    stats: &mut impl StatsRecorder,
+) -> Result<usize, CompressError> {
+    let mut dict = HashTableU16Stack::<LEN>::new();
+    init_dict(&mut dict, &mut dict_data);
+    let dict_len = dict_data.len();
+    compress_internal::<_, USE_DICT, _, _>(input, 0, output, &mut dict, dict_data, dict_len, stats)
+}
+
 #[inline]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
 fn init_dict<T: HashTable>(dict: &mut T, dict_data: &mut &[u8]) {
     if dict_data.len() > WINDOW_SIZE {
         *dict_data = &dict_data[dict_data.len() - WINDOW_SIZE..];
@@ -646,6 +994,186 @@ pub const fn get_maximum_output_size(input_len: usize) -> usize {
     16 + 4 + (input_len * 110 / 100)
 }
 
+/// The `MEMORY_USAGE` of the block functions and `FrameEncoder`: a 16KB hash table, 4096 entries.
+pub const DEFAULT_MEMORY_USAGE: u32 = 14;
+
+/// Block compression with a hash table of `2^MEMORY_USAGE` bytes, like `LZ4_MEMORY_USAGE` in the
+/// reference implementation.
+///
+/// The free functions of this module use [`DEFAULT_MEMORY_USAGE`]. A larger table finds more
+/// matches and improves the ratio on large inputs, a smaller one compresses small inputs faster
+/// and needs less memory. `MEMORY_USAGE` ranges from 10 (1KB) to 18 (256KB); inputs below 64KB
+/// use a table of the same length with 16 bit entries, at half the memory, and inputs up to 1KB
+/// a table on the stack of 256, 512 or 1024 entries, the next power of two of the input length
+/// but at most the `MEMORY_USAGE` length. Each size used is compiled separately, and an out of
+/// range size fails to compile.
+///
+/// # Example
+#[cfg_attr(feature = "block", doc = "```")]
+#[cfg_attr(not(feature = "block"), doc = "```ignore")]
+/// use lz4_flex_wasm_simd::block::{decompress, Compressor};
+/// let input: Vec<u8> = br#"{"id":1,"tags":["a","b"]},"#.repeat(100);
+/// let compressed = Compressor::<16>::compress(&input);
+/// assert_eq!(decompress(&compressed, input.len()).unwrap(), input);
+/// ```
+///
+/// ```compile_fail
+/// lz4_flex_wasm_simd::block::Compressor::<20>::compress(b"too large");
+/// ```
+#[derive(Debug, Clone, Copy)]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+pub struct Compressor<const MEMORY_USAGE: u32>;
+
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+impl<const MEMORY_USAGE: u32> Compressor<MEMORY_USAGE> {
+    /// See [`compress_into`].
+    #[inline]
+    pub fn compress_into(input: &[u8], output: &mut [u8]) -> Result<usize, CompressError> {
+        compress_into_sink_with_dict::<MEMORY_USAGE, false>(
+            input,
+            &mut SliceSink::new(output, 0),
+            b"",
+            &mut (),
+        )
+    }
+
+    /// See [`compress_into_with_dict`].
+    #[inline]
+    #[cfg(any(feature = "block", test))]
+    pub fn compress_into_with_dict(
+        input: &[u8],
+        output: &mut [u8],
+        dict_data: &[u8],
+    ) -> Result<usize, CompressError> {
+        compress_into_sink_with_dict::<MEMORY_USAGE, true>(
+            input,
+            &mut SliceSink::new(output, 0),
+            dict_data,
+            &mut (),
+        )
+    }
+
+    /// See [`compress_dest_size`].
+    pub fn compress_dest_size(input: &[u8], output: &mut [u8]) -> (usize, usize) {
+        if output.len() >= get_maximum_output_size(input.len()) {
+            let written = Self::compress_into(input, output).unwrap();
+            return (input.len(), written);
+        }
+        if output.is_empty() {
+            return (0, 0);
+        }
+        let output = &mut SliceSink::new(output, 0);
+        let result = if input.len() < u16::MAX as usize {
+            let mut dict = HashTableU16::<MEMORY_USAGE>::new();
+            compress_generic::<_, false, true, _, _>(input, 0, output, &mut dict, b"", 0, &mut ())
+        } else {
+            let mut dict = HashTableU32::<MEMORY_USAGE>::new();
+            compress_generic::<_, false, true, _, _>(input, 0, output, &mut dict, b"", 0, &mut ())
+        };
+        // Compressing into limited room never fails.
+        result.unwrap()
+    }
+
+    /// See [`compress_into_uninit`].
+    #[inline]
+    #[cfg(any(feature = "block", test))]
+    pub fn compress_into_uninit<'a>(
+        input: &[u8],
+        output: &'a mut [MaybeUninit<u8>],
+    ) -> Result<&'a mut [u8], CompressError> {
+        compress_into_uninit_with_dict::<MEMORY_USAGE, false>(input, output, b"", &mut ())
+    }
+
+    /// See [`compress_append`].
+    #[inline]
+    #[cfg(any(feature = "block", test))]
+    pub fn compress_append(input: &[u8], vec: &mut Vec<u8>) -> usize {
+        let max_compressed_size = get_maximum_output_size(input.len());
+        let old_len = vec.len();
+        #[cfg(feature = "safe-encode")]
+        {
+            vec.resize(old_len + max_compressed_size, 0);
+            let compressed_len = Self::compress_into(input, &mut vec[old_len..]).unwrap();
+            vec.truncate(old_len + compressed_len);
+            compressed_len
+        }
+        #[cfg(not(feature = "safe-encode"))]
+        {
+            vec.reserve(max_compressed_size);
+            let compressed_len = Self::compress_into_uninit(input, vec.spare_capacity_mut())
+                .unwrap()
+                .len();
+            // SAFETY: the compressor initialized the `compressed_len` bytes after `old_len`.
+            unsafe {
+                vec.set_len(old_len + compressed_len);
+            }
+            compressed_len
+        }
+    }
+
+    /// See [`compress_prepend_size`].
+    #[inline]
+    pub fn compress_prepend_size(input: &[u8]) -> Vec<u8> {
+        compress_into_vec_with_dict::<MEMORY_USAGE, false>(input, true, b"", &mut ())
+    }
+
+    /// See [`compress`].
+    #[inline]
+    pub fn compress(input: &[u8]) -> Vec<u8> {
+        compress_into_vec_with_dict::<MEMORY_USAGE, false>(input, false, b"", &mut ())
+    }
+
+    /// See [`compress_with_dict`].
+    #[inline]
+    #[cfg(any(feature = "block", test))]
+    pub fn compress_with_dict(input: &[u8], ext_dict: &[u8]) -> Vec<u8> {
+        compress_into_vec_with_dict::<MEMORY_USAGE, true>(input, false, ext_dict, &mut ())
+    }
+
+    /// See [`compress_prepend_size_with_dict`].
+    #[inline]
+    #[cfg(any(feature = "block", test))]
+    pub fn compress_prepend_size_with_dict(input: &[u8], ext_dict: &[u8]) -> Vec<u8> {
+        compress_into_vec_with_dict::<MEMORY_USAGE, true>(input, true, ext_dict, &mut ())
+    }
+
+    /// See [`compress_into_with_stats`].
+    #[inline]
+    #[cfg(any(feature = "block", test))]
+    pub fn compress_into_with_stats(
+        input: &[u8],
+        output: &mut [u8],
+        stats: &mut CompressStats,
+    ) -> Result<usize, CompressError> {
+        compress_into_sink_with_dict::<MEMORY_USAGE, false>(
+            input,
+            &mut SliceSink::new(output, 0),
+            b"",
+            stats,
+        )
+    }
+
+    /// See [`compress_with_stats`].
+    #[inline]
+    #[cfg(any(feature = "block", test))]
+    pub fn compress_with_stats(input: &[u8]) -> (Vec<u8>, CompressStats) {
+        let mut stats = CompressStats::new();
+        let compressed =
+            compress_into_vec_with_dict::<MEMORY_USAGE, false>(input, false, b"", &mut stats);
+        (compressed, stats)
+    }
+
+    /// See [`compress_with_dict_and_stats`].
+    #[inline]
+    #[cfg(any(feature = "block", test))]
+    pub fn compress_with_dict_and_stats(input: &[u8], ext_dict: &[u8]) -> (Vec<u8>, CompressStats) {
+        let mut stats = CompressStats::new();
+        let compressed =
+            compress_into_vec_with_dict::<MEMORY_USAGE, true>(input, false, ext_dict, &mut stats);
+        (compressed, stats)
+    }
+}
+
 /// Compress all bytes of `input` into `output`.
 /// The method chooses an appropriate hashtable to lookup duplicates.
 /// output should be preallocated with a size of
@@ -653,8 +1181,9 @@ pub const fn get_maximum_output_size(input_len: usize) -> usize {
 ///
 /// Returns the number of bytes written (compressed) into `output`.
 #[inline]
+#[cfg(any(feature = "block", test))]
 pub fn compress_into(input: &[u8], output: &mut [u8]) -> Result<usize, CompressError> {
-    compress_into_sink_with_dict::<false>(input, &mut SliceSink::new(output, 0), b"")
+    Compressor::<DEFAULT_MEMORY_USAGE>::compress_into(input, output)
 }
 
 /// Compress all bytes of `input` into `output`.
@@ -664,19 +1193,88 @@ pub fn compress_into(input: &[u8], output: &mut [u8]) -> Result<usize, CompressE
 ///
 /// Returns the number of bytes written (compressed) into `output`.
 #[inline]
+#[cfg(any(feature = "block", test))]
 pub fn compress_into_with_dict(
     input: &[u8],
     output: &mut [u8],
     dict_data: &[u8],
 ) -> Result<usize, CompressError> {
-    compress_into_sink_with_dict::<true>(input, &mut SliceSink::new(output, 0), dict_data)
+    Compressor::<DEFAULT_MEMORY_USAGE>::compress_into_with_dict(input, output, dict_data)
 }
 
+/// Compress as much of `input` as fits into `output`, like `LZ4_compress_destSize`.
+///
+/// Useful for filling fixed-size pages: `output` does not need room for
+/// `get_maximum_output_size` bytes. Returns `(consumed, written)`, where `output[..written]` is a
+/// block that decompresses to `input[..consumed]`. If all of `input` fits, `consumed` is
+/// `input.len()`. An empty `output` returns `(0, 0)`.
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+pub fn compress_dest_size(input: &[u8], output: &mut [u8]) -> (usize, usize) {
+    Compressor::<DEFAULT_MEMORY_USAGE>::compress_dest_size(input, output)
+}
+
+/// Compress all bytes of `input` into `output`, which does not need to be initialized.
+/// output should be preallocated with a size of
+/// `get_maximum_output_size`.
+///
+/// With `safe-encode` the compressor cannot write to uninitialized memory, so `output` is zeroed
+/// first.
+///
+/// Returns the compressed prefix of `output`.
 #[inline]
-fn compress_into_vec_with_dict<const USE_DICT: bool>(
+#[cfg(any(feature = "block", test))]
+pub fn compress_into_uninit<'a>(
+    input: &[u8],
+    output: &'a mut [MaybeUninit<u8>],
+) -> Result<&'a mut [u8], CompressError> {
+    Compressor::<DEFAULT_MEMORY_USAGE>::compress_into_uninit(input, output)
+}
+
+#[cfg(feature = "safe-encode")]
+#[inline]
+#[cfg(any(feature = "block", test))]
+fn compress_into_uninit_with_dict<'a, const MEMORY_USAGE: u32, const USE_DICT: bool>(
+    input: &[u8],
+    output: &'a mut [MaybeUninit<u8>],
+    dict_data: &[u8],
+    stats: &mut impl StatsRecorder,
+) -> Result<&'a mut [u8], CompressError> {
+    let output = super::zero_init(output);
+    let compressed_len = compress_into_sink_with_dict::<MEMORY_USAGE, USE_DICT>(
+        input,
+        &mut SliceSink::new(output, 0),
+        dict_data,
+        stats,
+    )?;
+    Ok(&mut output[..compressed_len])
+}
+
+#[cfg(not(feature = "safe-encode"))]
+#[inline]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+fn compress_into_uninit_with_dict<'a, const MEMORY_USAGE: u32, const USE_DICT: bool>(
+    input: &[u8],
+    output: &'a mut [MaybeUninit<u8>],
+    dict_data: &[u8],
+    stats: &mut impl StatsRecorder,
+) -> Result<&'a mut [u8], CompressError> {
+    let compressed_len = compress_into_sink_with_dict::<MEMORY_USAGE, USE_DICT>(
+        input,
+        &mut PtrSink::from_uninit(output, 0),
+        dict_data,
+        stats,
+    )?;
+    // SAFETY: the compressor initialized the first `compressed_len` bytes.
+    Ok(unsafe { super::assume_init_prefix(output, compressed_len) })
+}
+
+#[inline]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+fn compress_into_vec_with_dict<const MEMORY_USAGE: u32, const USE_DICT: bool>(
     input: &[u8],
     prepend_size: bool,
     mut dict_data: &[u8],
+    stats: &mut impl StatsRecorder,
 ) -> Vec<u8> {
     let prepend_size_num_bytes = if prepend_size { 4 } else { 0 };
     let max_compressed_size = get_maximum_output_size(input.len()) + prepend_size_num_bytes;
@@ -692,9 +1290,13 @@ fn compress_into_vec_with_dict<const USE_DICT: bool>(
         } else {
             &mut compressed
         };
-        let compressed_len =
-            compress_into_sink_with_dict::<USE_DICT>(input, &mut SliceSink::new(out, 0), dict_data)
-                .unwrap();
+        let compressed_len = compress_into_sink_with_dict::<MEMORY_USAGE, USE_DICT>(
+            input,
+            &mut SliceSink::new(out, 0),
+            dict_data,
+            stats,
+        )
+        .unwrap();
 
         compressed.truncate(prepend_size_num_bytes + compressed_len);
         compressed
@@ -702,18 +1304,17 @@ fn compress_into_vec_with_dict<const USE_DICT: bool>(
     #[cfg(not(feature = "safe-encode"))]
     let mut compressed = {
         let mut vec = Vec::with_capacity(max_compressed_size);
-        let start_pos = if prepend_size {
+        if prepend_size {
             vec.extend_from_slice(&(input.len() as u32).to_le_bytes());
-            4
-        } else {
-            0
-        };
-        let compressed_len = compress_into_sink_with_dict::<USE_DICT>(
+        }
+        let compressed_len = compress_into_uninit_with_dict::<MEMORY_USAGE, USE_DICT>(
             input,
-            &mut PtrSink::from_vec(&mut vec, start_pos),
+            vec.spare_capacity_mut(),
             dict_data,
+            stats,
         )
-        .unwrap();
+        .unwrap()
+        .len();
         unsafe {
             vec.set_len(prepend_size_num_bytes + compressed_len);
         }
@@ -724,30 +1325,75 @@ fn compress_into_vec_with_dict<const USE_DICT: bool>(
     compressed
 }
 
+/// Compress all bytes of `input` and append them to `vec`, reserving
+/// `get_maximum_output_size` bytes of spare capacity for the block.
+///
+/// Returns the number of bytes appended.
+#[inline]
+#[cfg(any(feature = "block", test))]
+pub fn compress_append(input: &[u8], vec: &mut Vec<u8>) -> usize {
+    Compressor::<DEFAULT_MEMORY_USAGE>::compress_append(input, vec)
+}
+
 /// Compress all bytes of `input` into `output`. The uncompressed size will be prepended as a little
 /// endian u32. Can be used in conjunction with `decompress_size_prepended`
 #[inline]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
 pub fn compress_prepend_size(input: &[u8]) -> Vec<u8> {
-    compress_into_vec_with_dict::<false>(input, true, b"")
+    Compressor::<DEFAULT_MEMORY_USAGE>::compress_prepend_size(input)
 }
 
 /// Compress all bytes of `input`.
 #[inline]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
 pub fn compress(input: &[u8]) -> Vec<u8> {
-    compress_into_vec_with_dict::<false>(input, false, b"")
+    Compressor::<DEFAULT_MEMORY_USAGE>::compress(input)
 }
 
 /// Compress all bytes of `input` with an external dictionary.
 #[inline]
+#[cfg(any(feature = "block", test))]
 pub fn compress_with_dict(input: &[u8], ext_dict: &[u8]) -> Vec<u8> {
-    compress_into_vec_with_dict::<true>(input, false, ext_dict)
+    Compressor::<DEFAULT_MEMORY_USAGE>::compress_with_dict(input, ext_dict)
 }
 
 /// Compress all bytes of `input` into `output`. The uncompressed size will be prepended as a little
 /// endian u32. Can be used in conjunction with `decompress_size_prepended_with_dict`
 #[inline]
+#[cfg(any(feature = "block", test))]
 pub fn compress_prepend_size_with_dict(input: &[u8], ext_dict: &[u8]) -> Vec<u8> {
-    compress_into_vec_with_dict::<true>(input, true, ext_dict)
+    Compressor::<DEFAULT_MEMORY_USAGE>::compress_prepend_size_with_dict(input, ext_dict)
+}
+
+/// Like [`compress_into`], and adds what the compressor did to `stats`.
+///
+/// Collecting statistics makes compression slower, the functions without them are unaffected.
+#[inline]
+#[cfg(any(feature = "block", test))]
+pub fn compress_into_with_stats(
+    input: &[u8],
+    output: &mut [u8],
+    stats: &mut CompressStats,
+) -> Result<usize, CompressError> {
+    Compressor::<DEFAULT_MEMORY_USAGE>::compress_into_with_stats(input, output, stats)
+}
+
+/// Like [`compress`], and returns what the compressor did.
+///
+/// Collecting statistics makes compression slower, the functions without them are unaffected.
+#[inline]
+#[cfg(any(feature = "block", test))]
+pub fn compress_with_stats(input: &[u8]) -> (Vec<u8>, CompressStats) {
+    Compressor::<DEFAULT_MEMORY_USAGE>::compress_with_stats(input)
+}
+
+/// Like [`compress_with_dict`], and returns what the compressor did.
+///
+/// Collecting statistics makes compression slower, the functions without them are unaffected.
+#[inline]
+#[cfg(any(feature = "block", test))]
+pub fn compress_with_dict_and_stats(input: &[u8], ext_dict: &[u8]) -> (Vec<u8>, CompressStats) {
+    Compressor::<DEFAULT_MEMORY_USAGE>::compress_with_dict_and_stats(input, ext_dict)
 }
 
 #[inline]
@@ -920,8 +1566,8 @@ mod tests {
         uncompressed[..output_start].copy_from_slice(&dict[dict_cutoff..]);
         let uncomp_len = {
             let mut sink = SliceSink::new(&mut uncompressed[..], output_start);
-            crate::block::decompress::decompress_internal::<true, _>(
-                &compressed,
+            crate::block::decompress::decompress_internal::<true, false, _>(
+                &compressed[..],
                 &mut sink,
                 &dict[..dict_cutoff],
             )
@@ -982,4 +1628,66 @@ mod tests {
             crate::block::decompress_size_prepended_with_dict(&compressed, &dict).unwrap();
         assert_eq!(decompressed, input);
     }
+
+    #[test]
+    fn test_stack_table_matches_heap_table() {
+        fn heap<const MEMORY_USAGE: u32>(input: &[u8]) -> Vec<u8> {
+            let mut out = vec![0; get_maximum_output_size(input.len())];
+            let mut dict = HashTableU16::<MEMORY_USAGE>::new();
+            let mut sink = SliceSink::new(&mut out, 0);
+            let len = compress_internal::<_, false, _, _>(
+                input,
+                0,
+                &mut sink,
+                &mut dict,
+                b"",
+                0,
+                &mut (),
+            )
+            .unwrap();
+            out.truncate(len);
+            out
+        }
+        // The stack table sized to the input gives the same block as the heap table of that
+        // length, and is never longer than the `MEMORY_USAGE` table.
+        let json = include_bytes!("../../bench-data/json_50kb.json");
+        for len in [20, 200, 256] {
+            let input = &json[5000..5000 + len];
+            assert_eq!(Compressor::<10>::compress(input), heap::<10>(input));
+            assert_eq!(Compressor::<12>::compress(input), heap::<10>(input));
+        }
+        for len in [300, 512] {
+            let input = &json[5000..5000 + len];
+            assert_eq!(Compressor::<10>::compress(input), heap::<10>(input));
+            assert_eq!(Compressor::<12>::compress(input), heap::<11>(input));
+        }
+        for len in [700, 1024] {
+            let input = &json[5000..5000 + len];
+            assert_eq!(Compressor::<11>::compress(input), heap::<11>(input));
+            assert_eq!(Compressor::<14>::compress(input), heap::<12>(input));
+        }
+    }
+
+    #[cfg(feature = "compress-prof")]
+    #[test]
+    fn test_run_profile_counts_runs() {
+        let text = &include_bytes!("../../bench-data/text_50kb.txt")[..200];
+        let mut input = text.to_vec();
+        input.extend_from_slice(&[0; 8]);
+        input.push(b'x');
+        input.extend_from_slice(text);
+        input.extend_from_slice(&[0; 300]);
+        input.extend_from_slice(text);
+
+        // Other tests compress at the same time, so the counters only grow by at least this
+        // input's run.
+        let before = read_compress_profile();
+        assert_eq!(
+            crate::block::decompress(&compress(&input), input.len()).unwrap(),
+            input
+        );
+        let after = read_compress_profile();
+        assert!(after.run_matches > before.run_matches);
+        assert!(after.run_bytes - before.run_bytes >= 292);
+    }
 }
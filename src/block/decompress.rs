@@ -1,118 +1,18 @@
 // Source provenance: derived from https://github.com/pseitz/lz4_flex (MIT), commit 975bfa7ac9583da879b5d7578b423232d84f69fe.
 //! The block decompression algorithm.
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+use crate::block::assume_init_prefix;
+#[cfg(any(feature = "block", test))]
+use crate::block::in_place::InPlaceInput;
+use crate::block::profile::prof_inc;
 use crate::block::{DecompressError, MINMATCH};
 use crate::fastcpy_unsafe;
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
 use crate::sink::SliceSink;
 use crate::sink::{PtrSink, Sink};
 #[allow(unused_imports)]
 use alloc::vec::Vec;
-
-#[allow(dead_code)]
-#[derive(Default, Clone, Copy)]
-pub(crate) struct DecompressProfileSnapshot {
-    pub fast_token_hits: u64,
-    pub duplicate_nonoverlap_wild: u64,
-    pub duplicate_near_end_exact_nonoverlap: u64,
-    pub duplicate_overlap_small_u64: u64,
-    pub duplicate_overlap_large_offset_chunk: u64,
-    pub duplicate_overlap_fallback_byte: u64,
-    pub copy_from_dict_calls: u64,
-    pub literal_bytes: u64,
-    pub match_bytes: u64,
-}
-
-#[cfg(feature = "decompress-prof")]
-mod profile {
-    use super::DecompressProfileSnapshot;
-    use core::sync::atomic::{AtomicU64, Ordering};
-
-    pub(super) static FAST_TOKEN_HITS: AtomicU64 = AtomicU64::new(0);
-    pub(super) static DUP_NONOVERLAP_WILD: AtomicU64 = AtomicU64::new(0);
-    pub(super) static DUP_NEAR_END_EXACT_NONOVERLAP: AtomicU64 = AtomicU64::new(0);
-    pub(super) static DUP_OVERLAP_SMALL_U64: AtomicU64 = AtomicU64::new(0);
-    pub(super) static DUP_OVERLAP_LARGE_OFFSET_CHUNK: AtomicU64 = AtomicU64::new(0);
-    pub(super) static DUP_OVERLAP_FALLBACK_BYTE: AtomicU64 = AtomicU64::new(0);
-    pub(super) static COPY_FROM_DICT_CALLS: AtomicU64 = AtomicU64::new(0);
-    pub(super) static LITERAL_BYTES: AtomicU64 = AtomicU64::new(0);
-    pub(super) static MATCH_BYTES: AtomicU64 = AtomicU64::new(0);
-
-    #[inline]
-    pub(super) fn inc(counter: &AtomicU64, n: u64) {
-        counter.fetch_add(n, Ordering::Relaxed);
-    }
-
-    pub(super) fn reset() {
-        FAST_TOKEN_HITS.store(0, Ordering::Relaxed);
-        DUP_NONOVERLAP_WILD.store(0, Ordering::Relaxed);
-        DUP_NEAR_END_EXACT_NONOVERLAP.store(0, Ordering::Relaxed);
-        DUP_OVERLAP_SMALL_U64.store(0, Ordering::Relaxed);
-        DUP_OVERLAP_LARGE_OFFSET_CHUNK.store(0, Ordering::Relaxed);
-        DUP_OVERLAP_FALLBACK_BYTE.store(0, Ordering::Relaxed);
-        COPY_FROM_DICT_CALLS.store(0, Ordering::Relaxed);
-        LITERAL_BYTES.store(0, Ordering::Relaxed);
-        MATCH_BYTES.store(0, Ordering::Relaxed);
-    }
-
-    pub(super) fn snapshot() -> DecompressProfileSnapshot {
-        DecompressProfileSnapshot {
-            fast_token_hits: FAST_TOKEN_HITS.load(Ordering::Relaxed),
-            duplicate_nonoverlap_wild: DUP_NONOVERLAP_WILD.load(Ordering::Relaxed),
-            duplicate_near_end_exact_nonoverlap: DUP_NEAR_END_EXACT_NONOVERLAP
-                .load(Ordering::Relaxed),
-            duplicate_overlap_small_u64: DUP_OVERLAP_SMALL_U64.load(Ordering::Relaxed),
-            duplicate_overlap_large_offset_chunk: DUP_OVERLAP_LARGE_OFFSET_CHUNK
-                .load(Ordering::Relaxed),
-            duplicate_overlap_fallback_byte: DUP_OVERLAP_FALLBACK_BYTE.load(Ordering::Relaxed),
-            copy_from_dict_calls: COPY_FROM_DICT_CALLS.load(Ordering::Relaxed),
-            literal_bytes: LITERAL_BYTES.load(Ordering::Relaxed),
-            match_bytes: MATCH_BYTES.load(Ordering::Relaxed),
-        }
-    }
-}
-
-#[cfg(not(feature = "decompress-prof"))]
-mod profile {
-    use super::DecompressProfileSnapshot;
-
-    #[inline]
-    #[allow(dead_code)]
-    pub(super) fn inc(_counter: &(), _n: u64) {}
-
-    #[inline]
-    pub(super) fn reset() {}
-
-    #[inline]
-    pub(super) fn snapshot() -> DecompressProfileSnapshot {
-        DecompressProfileSnapshot::default()
-    }
-}
-
-#[cfg(feature = "decompress-prof")]
-macro_rules! prof_inc {
-    ($counter:ident, $n:expr) => {
-        crate::block::decompress::profile::inc(
-            &crate::block::decompress::profile::$counter,
-            $n as u64,
-        )
-    };
-}
-
-#[cfg(not(feature = "decompress-prof"))]
-macro_rules! prof_inc {
-    ($counter:ident, $n:expr) => {
-        let _ = &$n;
-    };
-}
-
-#[allow(dead_code)]
-pub(crate) fn reset_decompress_profile() {
-    profile::reset();
-}
-
-#[allow(dead_code)]
-pub(crate) fn read_decompress_profile() -> DecompressProfileSnapshot {
-    profile::snapshot()
-}
+use core::mem::MaybeUninit;
 
 /// Copies data to output_ptr by self-referential copy from start and match_length
 #[inline]
@@ -133,14 +33,14 @@ unsafe fn duplicate(
     if offset < match_length + 16 - 1 || remaining_output < match_length + 16 - 1 {
         // If we're close to output end but match does not overlap, prefer exact copy.
         if offset >= match_length {
-            prof_inc!(DUP_NEAR_END_EXACT_NONOVERLAP, 1);
+            prof_inc!(DupNearEndExactNonoverlap, 1);
             core::ptr::copy_nonoverlapping(start, *output_ptr, match_length);
             *output_ptr = output_ptr.add(match_length);
             return;
         }
         duplicate_overlapping(output_ptr, start, match_length);
     } else {
-        prof_inc!(DUP_NONOVERLAP_WILD, 1);
+        prof_inc!(DupNonoverlapWild, 1);
         debug_assert!(
             output_ptr.add(match_length / 16 * 16 + ((match_length % 16) != 0) as usize * 16)
                 <= output_end
@@ -167,6 +67,30 @@ fn wild_copy_from_src_16(mut source: *const u8, mut dst_ptr: *mut u8, num_items:
     }
 }
 
+/// Expands offsets below 16 with a SIMD byte shuffle, on targets that have one.
+#[inline]
+unsafe fn duplicate_overlapping_small_offset_simd(
+    output_ptr: &mut *mut u8,
+    offset: usize,
+    match_length: usize,
+) -> bool {
+    #[cfg(any(
+        all(target_arch = "wasm32", target_feature = "simd128"),
+        target_arch = "x86_64",
+        target_arch = "aarch64"
+    ))]
+    if (1..16).contains(&offset)
+        && match_length >= 16
+        && crate::simd::duplicate_overlapping_simd(*output_ptr, offset, match_length)
+    {
+        prof_inc!(DupOverlapSmallSimd, 1);
+        *output_ptr = output_ptr.add(match_length);
+        return true;
+    }
+    let _ = (output_ptr, offset, match_length);
+    false
+}
+
 #[inline]
 unsafe fn duplicate_overlapping_small_offset_u64(
     output_ptr: &mut *mut u8,
@@ -177,7 +101,7 @@ unsafe fn duplicate_overlapping_small_offset_u64(
     if !matches!(offset, 1 | 2 | 4 | 8) || match_length < 8 {
         return false;
     }
-    prof_inc!(DUP_OVERLAP_SMALL_U64, 1);
+    prof_inc!(DupOverlapSmallU64, 1);
 
     let pattern64 = match offset {
         1 => {
@@ -222,11 +146,14 @@ unsafe fn duplicate_overlapping(
     match_length: usize,
 ) {
     let offset = output_ptr.offset_from(start) as usize;
+    if duplicate_overlapping_small_offset_simd(output_ptr, offset, match_length) {
+        return;
+    }
     if duplicate_overlapping_small_offset_u64(output_ptr, start, offset, match_length) {
         return;
     }
     if offset >= 16 {
-        prof_inc!(DUP_OVERLAP_LARGE_OFFSET_CHUNK, 1);
+        prof_inc!(DupOverlapLargeOffsetChunk, 1);
         let mut dst = *output_ptr;
         let mut remaining = match_length;
         while remaining != 0 {
@@ -239,7 +166,7 @@ unsafe fn duplicate_overlapping(
         *output_ptr = dst;
         return;
     }
-    prof_inc!(DUP_OVERLAP_FALLBACK_BYTE, 1);
+    prof_inc!(DupOverlapFallbackByte, 1);
 
     // There is an edge case when output_ptr == start, which causes the decoder to potentially
     // expose up to match_length bytes of uninitialized data in the decompression buffer.
@@ -276,7 +203,7 @@ unsafe fn copy_from_dict(
     offset: usize,
     match_length: usize,
 ) -> usize {
-    prof_inc!(COPY_FROM_DICT_CALLS, 1);
+    prof_inc!(CopyFromDictCalls, 1);
     // If we're here we know offset > output pos, so we have at least 1 byte to copy from dict
     debug_assert!(output_ptr.offset_from(output_base) >= 0);
     debug_assert!(offset > output_ptr.offset_from(output_base) as usize);
@@ -314,7 +241,7 @@ unsafe fn copy_from_dict(
 pub(super) fn read_integer_ptr(
     input_ptr: &mut *const u8,
     _input_ptr_end: *const u8,
-) -> Result<usize, DecompressError> {
+) -> Option<usize> {
     // We start at zero and count upwards.
     let mut n: usize = 0;
     // If this byte takes value 255 (the maximum value it can take), another byte is read
@@ -325,7 +252,7 @@ pub(super) fn read_integer_ptr(
         // could be skipped with unchecked-decode
         {
             if *input_ptr >= _input_ptr_end {
-                return Err(DecompressError::ExpectedAnotherByte);
+                return None;
             }
         }
         let extra = unsafe { input_ptr.read() };
@@ -341,7 +268,7 @@ pub(super) fn read_integer_ptr(
     // 255, 255, 255, 8
     // 111, 111, 111, 101
 
-    Ok(n)
+    Some(n)
 }
 
 /// Read a little-endian 16-bit integer from the input stream.
@@ -377,18 +304,79 @@ fn does_token_fit(token: u8) -> bool {
         || (token & FIT_TOKEN_MASK_MATCH) == FIT_TOKEN_MASK_MATCH)
 }
 
+/// The block read by [`decompress_internal`]: a slice, or a range of the output buffer when
+/// decompressing in place.
+pub(crate) trait BlockInput {
+    /// Returns the length of the block.
+    fn len(&self) -> usize;
+
+    /// Returns a pointer to the block, given the output it is decompressed into.
+    ///
+    /// # Safety
+    /// `output_base` must point to the start of the output.
+    unsafe fn base_ptr(&self, output_base: *mut u8) -> *const u8;
+
+    /// Whether the block is stored in the output, which limits the output to the input not read
+    /// yet.
+    #[inline]
+    fn in_place(&self) -> bool {
+        false
+    }
+}
+
+impl BlockInput for &[u8] {
+    #[inline]
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+
+    #[inline]
+    unsafe fn base_ptr(&self, _output_base: *mut u8) -> *const u8 {
+        self.as_ptr()
+    }
+}
+
+#[cfg(any(feature = "block", test))]
+impl BlockInput for InPlaceInput {
+    #[inline]
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// The block is read through the output pointer, which is also used to write over it.
+    #[inline]
+    unsafe fn base_ptr(&self, output_base: *mut u8) -> *const u8 {
+        output_base.add(self.start)
+    }
+
+    #[inline]
+    fn in_place(&self) -> bool {
+        true
+    }
+}
+
 /// Decompress all bytes of `input` into `output`.
 ///
-/// Returns the number of bytes written (decompressed) into `output`.
+/// With `PARTIAL`, decoding stops once `output` is full instead of failing with
+/// `OutputTooSmall`, and the input after that point is not read.
+///
+/// Returns the number of bytes written (decompressed) into `output`. Errors report the input
+/// position of the failing sequence's token and the output position at the time of failure.
 #[inline]
-pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
-    input: &[u8],
+pub(crate) fn decompress_internal<const USE_DICT: bool, const PARTIAL: bool, S: Sink>(
+    input: impl BlockInput,
     output: &mut S,
     ext_dict: &[u8],
 ) -> Result<usize, DecompressError> {
+    if PARTIAL && output.pos() == output.capacity() {
+        return Ok(0);
+    }
     // Prevent segfault for empty input
-    if input.is_empty() {
-        return Err(DecompressError::ExpectedAnotherByte);
+    if input.len() == 0 {
+        return Err(DecompressError::ExpectedAnotherByte {
+            input_pos: 0,
+            output_pos: output.pos(),
+        });
     }
 
     let ext_dict = if USE_DICT {
@@ -400,11 +388,12 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
     };
     let output_base = unsafe { output.base_mut_ptr() };
     let output_end = unsafe { output_base.add(output.capacity()) };
-    let output_start_pos_ptr = unsafe { output.base_mut_ptr().add(output.pos()) };
+    let output_start_pos_ptr = unsafe { output_base.add(output.pos()) };
     let mut output_ptr = output_start_pos_ptr;
 
-    let mut input_ptr = input.as_ptr();
-    let input_ptr_end = unsafe { input.as_ptr().add(input.len()) };
+    let input_base = unsafe { input.base_ptr(output_base) };
+    let mut input_ptr = input_base;
+    let input_ptr_end = unsafe { input_base.add(input.len()) };
     let safe_distance_from_end =  (16 /* literal copy */ +  2 /* u16 match offset */ + 1 /* The next token to read (we can skip the check) */).min(input.len()) ;
     let input_ptr_safe = unsafe { input_ptr_end.sub(safe_distance_from_end) };
 
@@ -429,8 +418,27 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
         // subtokens, the higher and the lower.
         // This token contains to 4-bit "fields", a higher and a lower, representing the literals'
         // length and the back reference's length, respectively.
+        let token_ptr = input_ptr;
+        // Every path below leaves at least one byte for the next token; with checked-decode
+        // that is verified instead of assumed.
+        #[cfg(feature = "checked-decode")]
+        {
+            if input_ptr >= input_ptr_end {
+                return Err(DecompressError::ExpectedAnotherByte {
+                    input_pos: input.len(),
+                    output_pos: unsafe { output_ptr.offset_from(output_base) as usize },
+                });
+            }
+        }
         let token = unsafe { input_ptr.read() };
         input_ptr = unsafe { input_ptr.add(1) };
+        // Positions reported in errors: the token of this sequence and the current output.
+        let error_pos = |output_ptr: *mut u8| unsafe {
+            (
+                token_ptr.offset_from(input_base) as usize,
+                output_ptr.offset_from(output_base) as usize,
+            )
+        };
 
         // Checking for hot-loop.
         // In most cases the metadata does fit in a single 1byte token (statistically) and we are in
@@ -439,15 +447,19 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
         // Ideally we want to check for safe output pos like: output.pos() <= safe_output_pos; But
         // that doesn't work when the safe_output_ptr is == output_ptr due to insufficient
         // capacity. So we use `<` instead of `<=`, which covers that case.
+        //
+        // In place, the 16 byte literal copy and the 18 byte match copy must also end before the
+        // unread input, which they do if the output is 16 bytes behind the literal.
         if does_token_fit(token)
             && (input_ptr as usize) <= input_ptr_safe as usize
             && output_ptr < safe_output_ptr
+            && (!input.in_place() || output_ptr as usize + 16 <= input_ptr as usize)
         {
             let literal_length = (token >> 4) as usize;
             let mut match_length = MINMATCH + (token & 0xF) as usize;
-            prof_inc!(FAST_TOKEN_HITS, 1);
-            prof_inc!(LITERAL_BYTES, literal_length);
-            prof_inc!(MATCH_BYTES, match_length);
+            prof_inc!(FastTokenHits, 1);
+            prof_inc!(LiteralBytes, literal_length);
+            prof_inc!(MatchBytes, match_length);
 
             // output_ptr <= safe_output_ptr should guarantee we have enough space in output
             debug_assert!(
@@ -470,7 +482,20 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
             let offset = read_u16_ptr(&mut input_ptr) as usize;
 
             let output_len = unsafe { output_ptr.offset_from(output_base) as usize };
-            let offset = offset.min(output_len + ext_dict.len());
+            let window = output_len + ext_dict.len();
+            // An offset of 0 would copy the match onto itself and leave the output uninitialized,
+            // so it is always rejected. Offsets past the window are rejected with checked-decode,
+            // like in the slow path below, and clamped otherwise, which keeps the copy in bounds
+            // but decodes garbage.
+            if offset == 0 || window == 0 || (cfg!(feature = "checked-decode") && offset > window) {
+                let (input_pos, output_pos) = error_pos(output_ptr);
+                return Err(DecompressError::OffsetOutOfBounds {
+                    input_pos,
+                    output_pos,
+                    offset,
+                });
+            }
+            let offset = offset.min(window);
 
             // Check if part of the match is in the external dict
             if USE_DICT && offset > output_len {
@@ -520,7 +545,18 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
             if literal_length == 15 {
                 // The literal_length length took the maximal value, indicating that there is more
                 // than 15 literal_length bytes. We read the extra integer.
-                literal_length += read_integer_ptr(&mut input_ptr, input_ptr_end)? as usize;
+                literal_length +=
+                    read_integer_ptr(&mut input_ptr, input_ptr_end).ok_or_else(|| {
+                        let (input_pos, output_pos) = error_pos(output_ptr);
+                        DecompressError::ExpectedAnotherByte {
+                            input_pos,
+                            output_pos,
+                        }
+                    })?;
+            }
+            if PARTIAL {
+                literal_length =
+                    literal_length.min(unsafe { output_end.offset_from(output_ptr) as usize });
             }
 
             // could be skipped with unchecked-decode
@@ -528,7 +564,12 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
                 // Check if literal is out of bounds for the input, and if there is enough space on
                 // the output
                 if literal_length > input_ptr_end as usize - input_ptr as usize {
-                    return Err(DecompressError::LiteralOutOfBounds);
+                    let (input_pos, output_pos) = error_pos(output_ptr);
+                    return Err(DecompressError::LiteralOutOfBounds {
+                        input_pos,
+                        output_pos,
+                        literal_length,
+                    });
                 }
                 if literal_length > unsafe { output_end.offset_from(output_ptr) as usize } {
                     return Err(DecompressError::OutputTooSmall {
@@ -539,11 +580,20 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
                 }
             }
             unsafe {
-                prof_inc!(LITERAL_BYTES, literal_length);
-                fastcpy_unsafe::slice_copy(input_ptr, output_ptr, literal_length);
+                prof_inc!(LiteralBytes, literal_length);
+                if input.in_place() {
+                    // The literal moves towards the start of the buffer, possibly over its own
+                    // bytes.
+                    core::ptr::copy(input_ptr, output_ptr, literal_length);
+                } else {
+                    fastcpy_unsafe::slice_copy(input_ptr, output_ptr, literal_length);
+                }
                 output_ptr = output_ptr.add(literal_length);
                 input_ptr = input_ptr.add(literal_length);
             }
+            if PARTIAL && output_ptr == output_end {
+                break;
+            }
         }
 
         // If the input stream is emptied, we break out of the loop. This is only the case
@@ -556,7 +606,11 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
         // could be skipped with unchecked-decode
         {
             if (input_ptr_end as usize) - (input_ptr as usize) < 2 {
-                return Err(DecompressError::ExpectedAnotherByte);
+                let (input_pos, output_pos) = error_pos(output_ptr);
+                return Err(DecompressError::ExpectedAnotherByte {
+                    input_pos,
+                    output_pos,
+                });
             }
         }
         let offset = read_u16_ptr(&mut input_ptr) as usize;
@@ -572,9 +626,15 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
         if match_length == MINMATCH + 15 {
             // The match length took the maximal value, indicating that there is more bytes. We
             // read the extra integer.
-            match_length += read_integer_ptr(&mut input_ptr, input_ptr_end)? as usize;
+            match_length += read_integer_ptr(&mut input_ptr, input_ptr_end).ok_or_else(|| {
+                let (input_pos, output_pos) = error_pos(output_ptr);
+                DecompressError::ExpectedAnotherByte {
+                    input_pos,
+                    output_pos,
+                }
+            })?;
         }
-        prof_inc!(MATCH_BYTES, match_length);
+        prof_inc!(MatchBytes, match_length);
 
         // We now copy from the already decompressed buffer. This allows us for storing duplicates
         // by simply referencing the other location.
@@ -582,8 +642,30 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
 
         // could be skipped with unchecked-decode
         {
-            if offset > output_len + ext_dict.len() {
-                return Err(DecompressError::OffsetOutOfBounds);
+            // Offset 0 would copy the match onto itself and leave the output uninitialized.
+            if offset == 0 || offset > output_len + ext_dict.len() {
+                let (input_pos, output_pos) = error_pos(output_ptr);
+                return Err(DecompressError::OffsetOutOfBounds {
+                    input_pos,
+                    output_pos,
+                    offset,
+                });
+            }
+            if PARTIAL {
+                match_length =
+                    match_length.min(unsafe { output_end.offset_from(output_ptr) as usize });
+            }
+            if input.in_place() {
+                // The match must end before the unread input, which also ends the output.
+                let unread_pos = unsafe { input_ptr.offset_from(output_base) as usize };
+                if output_len + match_length > unread_pos {
+                    let (input_pos, _) = error_pos(output_ptr);
+                    return Err(DecompressError::InPlaceOverlap {
+                        input_pos,
+                        output_end: output_len + match_length,
+                        unread_pos,
+                    });
+                }
             }
             if match_length > unsafe { output_end.offset_from(output_ptr) as usize } {
                 return Err(DecompressError::OutputTooSmall {
@@ -598,10 +680,17 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
                 copy_from_dict(output_base, &mut output_ptr, ext_dict, offset, match_length)
             };
             if copied == match_length {
+                if PARTIAL && output_ptr == output_end {
+                    break;
+                }
                 // could be skipped with unchecked-decode
                 {
                     if input_ptr >= input_ptr_end {
-                        return Err(DecompressError::ExpectedAnotherByte);
+                        let (input_pos, output_pos) = error_pos(output_ptr);
+                        return Err(DecompressError::ExpectedAnotherByte {
+                            input_pos,
+                            output_pos,
+                        });
                     }
                 }
 
@@ -618,13 +707,26 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
         debug_assert!(start_ptr >= output_base);
         debug_assert!(start_ptr < output_end);
         debug_assert!(unsafe { output_end.offset_from(start_ptr) as usize } >= match_length);
+        // In place, the wild copies must also end before the unread input.
+        let write_end = if input.in_place() {
+            input_ptr as *mut u8
+        } else {
+            output_end
+        };
         unsafe {
-            duplicate(&mut output_ptr, output_end, start_ptr, match_length);
+            duplicate(&mut output_ptr, write_end, start_ptr, match_length);
+        }
+        if PARTIAL && output_ptr == output_end {
+            break;
         }
         // could be skipped with unchecked-decode
         {
             if input_ptr >= input_ptr_end {
-                return Err(DecompressError::ExpectedAnotherByte);
+                let (input_pos, output_pos) = error_pos(output_ptr);
+                return Err(DecompressError::ExpectedAnotherByte {
+                    input_pos,
+                    output_pos,
+                });
             }
         }
     }
@@ -637,20 +739,162 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
 /// Decompress all bytes of `input` into `output`.
 /// `output` should be preallocated with a size of of the uncompressed data.
 #[inline]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
 pub fn decompress_into(input: &[u8], output: &mut [u8]) -> Result<usize, DecompressError> {
-    decompress_internal::<false, _>(input, &mut SliceSink::new(output, 0), b"")
+    decompress_internal::<false, false, _>(input, &mut SliceSink::new(output, 0), b"")
+}
+
+/// Decompress all bytes of `input` into `output`, which does not need to be initialized.
+/// `output` should be preallocated with a size of of the uncompressed data.
+///
+/// Returns the decompressed prefix of `output`.
+#[inline]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+pub fn decompress_into_uninit<'a>(
+    input: &[u8],
+    output: &'a mut [MaybeUninit<u8>],
+) -> Result<&'a mut [u8], DecompressError> {
+    decompress_into_uninit_with_dict::<false>(input, output, b"")
+}
+
+#[inline]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+fn decompress_into_uninit_with_dict<'a, const USE_DICT: bool>(
+    input: &[u8],
+    output: &'a mut [MaybeUninit<u8>],
+    ext_dict: &[u8],
+) -> Result<&'a mut [u8], DecompressError> {
+    let decomp_len = decompress_internal::<USE_DICT, false, _>(
+        input,
+        &mut PtrSink::from_uninit(output, 0),
+        ext_dict,
+    )?;
+    // SAFETY: the decoder initialized the first `decomp_len` bytes.
+    Ok(unsafe { assume_init_prefix(output, decomp_len) })
+}
+
+/// Decompress all bytes of `input` and append them to `vec`, writing at most `max_len` bytes
+/// into its spare capacity. The existing contents of `vec` are not visible to the block, so
+/// many independent blocks can be collected into one buffer.
+///
+/// Returns the number of bytes appended. On error `vec` is left unchanged, apart from its
+/// capacity.
+#[inline]
+#[cfg(any(feature = "block", test))]
+pub fn decompress_append(
+    input: &[u8],
+    vec: &mut Vec<u8>,
+    max_len: usize,
+) -> Result<usize, DecompressError> {
+    vec.reserve(max_len);
+    let old_len = vec.len();
+    let decomp_len = decompress_into_uninit(input, &mut vec.spare_capacity_mut()[..max_len])?.len();
+    // SAFETY: the decoder initialized the `decomp_len` bytes after `old_len`.
+    unsafe {
+        vec.set_len(old_len + decomp_len);
+    }
+    Ok(decomp_len)
+}
+
+/// Decompress `input` into `vec` right after `vec[..pos]`, which serves as the prefix for
+/// matches. At most `max_len` bytes are written, into spare capacity that is not zeroed first.
+///
+/// Returns the number of bytes written; `vec.len()` is at least `pos` plus that afterwards.
+/// The vec is never shortened.
+#[allow(dead_code)]
+#[inline]
+pub(crate) fn decompress_into_vec(
+    input: &[u8],
+    vec: &mut Vec<u8>,
+    pos: usize,
+    max_len: usize,
+) -> Result<usize, DecompressError> {
+    let old_len = vec.len();
+    assert!(pos <= old_len);
+    vec.reserve((pos + max_len).saturating_sub(old_len));
+    // SAFETY: `vec[..pos]` is initialized and the rest is reserved capacity.
+    let output = unsafe {
+        core::slice::from_raw_parts_mut(vec.as_mut_ptr() as *mut MaybeUninit<u8>, pos + max_len)
+    };
+    let decomp_len =
+        decompress_internal::<false, false, _>(input, &mut PtrSink::from_uninit(output, pos), b"")?;
+    // SAFETY: the decoder initialized `vec[pos..pos + decomp_len]`, and `vec[..old_len]` was
+    // initialized before. Bytes the decoder wrote past its end were copied from initialized bytes.
+    unsafe {
+        vec.set_len(old_len.max(pos + decomp_len));
+    }
+    Ok(decomp_len)
+}
+
+/// Decompress the first `target_len` bytes of `input` into `output`.
+///
+/// Decoding stops as soon as `target_len` bytes are produced, so `input` may also be a truncated
+/// block as long as it covers them. `target_len` is capped at `output.len()`, and bytes of
+/// `output` past it are left untouched.
+///
+/// Returns the number of bytes written, which is less than `target_len` only if the whole block
+/// decompresses to fewer bytes.
+#[inline]
+#[cfg(any(feature = "block", test))]
+pub fn decompress_partial_into(
+    input: &[u8],
+    output: &mut [u8],
+    target_len: usize,
+) -> Result<usize, DecompressError> {
+    let target_len = target_len.min(output.len());
+    decompress_internal::<false, true, _>(
+        input,
+        &mut SliceSink::new(&mut output[..target_len], 0),
+        b"",
+    )
 }
 
 /// Decompress all bytes of `input` into `output`.
 ///
 /// Returns the number of bytes written (decompressed) into `output`.
 #[inline]
+#[cfg(any(feature = "block", test))]
 pub fn decompress_into_with_dict(
     input: &[u8],
     output: &mut [u8],
     ext_dict: &[u8],
 ) -> Result<usize, DecompressError> {
-    decompress_internal::<true, _>(input, &mut SliceSink::new(output, 0), ext_dict)
+    decompress_internal::<true, false, _>(input, &mut SliceSink::new(output, 0), ext_dict)
+}
+
+/// Decompress all bytes of `input` into `output[out_start..]`, letting matches reference
+/// `output[..out_start]`.
+///
+/// This is prefix mode: the data the block was compressed against already sits right before the
+/// output position in the same buffer, as for linked blocks in a frame, so no separate `ext_dict`
+/// slice is needed. Blocks compressed with [`compress_with_dict`](super::compress_with_dict)
+/// decode this way when the dictionary is placed in front of the output.
+///
+/// Returns the number of bytes written after `out_start`.
+///
+/// # Panics
+/// Panics if `out_start` is greater than `output.len()`.
+///
+/// # Example
+#[cfg_attr(feature = "block", doc = "```")]
+#[cfg_attr(not(feature = "block"), doc = "```ignore")]
+/// use lz4_flex_wasm_simd::block::{compress_with_dict, decompress_into_with_prefix};
+/// let (first, second) = (b"chunk one of a column", b"chunk two of a column");
+/// let block = compress_with_dict(second, first);
+///
+/// let mut output = vec![0u8; first.len() + second.len()];
+/// output[..first.len()].copy_from_slice(first);
+/// let len = decompress_into_with_prefix(&block, &mut output, first.len()).unwrap();
+/// assert_eq!(&output[first.len()..first.len() + len], second);
+/// ```
+#[inline]
+#[cfg(any(feature = "block", test))]
+pub fn decompress_into_with_prefix(
+    input: &[u8],
+    output: &mut [u8],
+    out_start: usize,
+) -> Result<usize, DecompressError> {
+    decompress_internal::<false, false, _>(input, &mut SliceSink::new(output, out_start), b"")
 }
 
 /// Decompress all bytes of `input` into a new vec.
@@ -660,6 +904,7 @@ pub fn decompress_into_with_dict(
 /// May panic if the parameter `min_uncompressed_size` is smaller than the
 /// uncompressed data.
 #[inline]
+#[cfg(any(feature = "block", test))]
 pub fn decompress_with_dict(
     input: &[u8],
     min_uncompressed_size: usize,
@@ -668,7 +913,7 @@ pub fn decompress_with_dict(
     // Allocate a vector to contain the decompressed stream.
     let mut vec = Vec::with_capacity(min_uncompressed_size);
     let decomp_len =
-        decompress_internal::<true, _>(input, &mut PtrSink::from_vec(&mut vec, 0), ext_dict)?;
+        decompress_into_uninit_with_dict::<true>(input, vec.spare_capacity_mut(), ext_dict)?.len();
     unsafe {
         vec.set_len(decomp_len);
     }
@@ -678,6 +923,7 @@ pub fn decompress_with_dict(
 /// Decompress all bytes of `input` into a new vec. The first 4 bytes are the uncompressed size in
 /// little endian. Can be used in conjunction with `compress_prepend_size`
 #[inline]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
 pub fn decompress_size_prepended(input: &[u8]) -> Result<Vec<u8>, DecompressError> {
     let (uncompressed_size, input) = super::uncompressed_size(input)?;
     decompress(input, uncompressed_size)
@@ -690,11 +936,11 @@ pub fn decompress_size_prepended(input: &[u8]) -> Result<Vec<u8>, DecompressErro
 /// May panic if the parameter `min_uncompressed_size` is smaller than the
 /// uncompressed data.
 #[inline]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
 pub fn decompress(input: &[u8], min_uncompressed_size: usize) -> Result<Vec<u8>, DecompressError> {
     // Allocate a vector to contain the decompressed stream.
     let mut vec = Vec::with_capacity(min_uncompressed_size);
-    let decomp_len =
-        decompress_internal::<true, _>(input, &mut PtrSink::from_vec(&mut vec, 0), b"")?;
+    let decomp_len = decompress_into_uninit(input, vec.spare_capacity_mut())?.len();
     unsafe {
         vec.set_len(decomp_len);
     }
@@ -704,6 +950,7 @@ pub fn decompress(input: &[u8], min_uncompressed_size: usize) -> Result<Vec<u8>,
 /// Decompress all bytes of `input` into a new vec. The first 4 bytes are the uncompressed size in
 /// little endian. Can be used in conjunction with `compress_prepend_size_with_dict`
 #[inline]
+#[cfg(any(feature = "block", test))]
 pub fn decompress_size_prepended_with_dict(
     input: &[u8],
     ext_dict: &[u8],
@@ -768,6 +1015,28 @@ mod test {
         decompress(&[0x40, b'a', 1, 0], 4).unwrap_err();
     }
 
+    #[cfg(feature = "checked-decode")]
+    #[test]
+    fn checked_offsets_in_fast_path() {
+        // A one byte literal with an 8 byte match, followed by enough literals to take the fast
+        // path for the first sequence.
+        let block = |offset: u8| {
+            let mut block = vec![0x14, b'a', offset, 0, 0xF0, 5];
+            block.extend_from_slice(&[b'b'; 20]);
+            block
+        };
+        let mut out = [0u8; 100];
+        assert_eq!(decompress_into(&block(1), &mut out).unwrap(), 29);
+        assert!(matches!(
+            decompress_into(&block(2), &mut out),
+            Err(DecompressError::OffsetOutOfBounds {
+                input_pos: 0,
+                output_pos: 1,
+                offset: 2,
+            })
+        ));
+    }
+
     #[test]
     fn overlap_offsets_1_to_16() {
         for offset in 1usize..=16 {
@@ -802,6 +1071,25 @@ mod test {
         assert_eq!(&out, b"klmnoklm");
     }
 
+    #[test]
+    fn decompress_into_vec_keeps_longer_vec() {
+        let prefix = b"the quick brown fox ";
+        let data = b"the quick brown fox jumps over the lazy dog, the quick brown fox";
+        let compressed = crate::block::compress_with_dict(data, prefix);
+
+        let mut vec = prefix.to_vec();
+        vec.resize(200, 0xAA);
+        let len = decompress_into_vec(&compressed, &mut vec, prefix.len(), data.len()).unwrap();
+        assert_eq!(len, data.len());
+        assert_eq!(vec.len(), 200);
+        assert_eq!(&vec[prefix.len()..prefix.len() + len], data);
+
+        let mut vec = prefix.to_vec();
+        let len = decompress_into_vec(&compressed, &mut vec, prefix.len(), data.len()).unwrap();
+        assert_eq!(vec.len(), prefix.len() + len);
+        assert_eq!(&vec[prefix.len()..], data);
+    }
+
     #[test]
     fn differential_against_upstream_lz4() {
         fn xorshift64(state: &mut u64) -> u64 {
@@ -1,88 +1,22 @@
 // Source provenance: derived from https://github.com/pseitz/lz4_flex (MIT), commit 975bfa7ac9583da879b5d7578b423232d84f69fe.
 //! The block decompression algorithm.
 
+#[cfg(any(feature = "block", test))]
+use crate::block::in_place::InPlaceInput;
+use crate::block::profile::prof_inc;
+use crate::block::sequence::{read_literal_length, read_match_length, read_u16};
 use crate::block::DecompressError;
 use crate::block::MINMATCH;
 use crate::sink::Sink;
 use crate::sink::SliceSink;
+#[cfg(any(feature = "block", test))]
+use core::mem::MaybeUninit;
 
 #[allow(unused_imports)]
 use alloc::vec;
 #[allow(unused_imports)]
 use alloc::vec::Vec;
 
-#[allow(dead_code)]
-#[derive(Default, Clone, Copy)]
-pub(crate) struct DecompressProfileSnapshot {
-    pub fast_token_hits: u64,
-    pub duplicate_nonoverlap_wild: u64,
-    pub duplicate_near_end_exact_nonoverlap: u64,
-    pub duplicate_overlap_small_u64: u64,
-    pub duplicate_overlap_large_offset_chunk: u64,
-    pub duplicate_overlap_fallback_byte: u64,
-    pub copy_from_dict_calls: u64,
-    pub literal_bytes: u64,
-    pub match_bytes: u64,
-}
-
-#[allow(dead_code)]
-pub(crate) fn reset_decompress_profile() {}
-
-#[allow(dead_code)]
-pub(crate) fn read_decompress_profile() -> DecompressProfileSnapshot {
-    DecompressProfileSnapshot::default()
-}
-
-/// Read an integer.
-///
-/// In LZ4, we encode small integers in a way that we can have an arbitrary number of bytes. In
-/// particular, we add the bytes repeatedly until we hit a non-0xFF byte. When we do, we add
-/// this byte to our sum and terminate the loop.
-///
-/// # Example
-///
-/// ```notest
-///     255, 255, 255, 4, 2, 3, 4, 6, 7
-/// ```
-///
-/// is encoded to _255 + 255 + 255 + 4 = 769_. The bytes after the first 4 is ignored, because
-/// 4 is the first non-0xFF byte.
-#[inline]
-pub(super) fn read_integer(input: &[u8], input_pos: &mut usize) -> Result<usize, DecompressError> {
-    // We start at zero and count upwards.
-    let mut n: usize = 0;
-    // If this byte takes value 255 (the maximum value it can take), another byte is read
-    // and added to the sum. This repeats until a byte lower than 255 is read.
-    loop {
-        // We add the next byte until we get a byte which we add to the counting variable.
-        let extra: u8 = *input
-            .get(*input_pos)
-            .ok_or(DecompressError::ExpectedAnotherByte)?;
-        *input_pos += 1;
-        n += extra as usize;
-
-        // We continue if we got 255, break otherwise.
-        if extra != 0xFF {
-            break;
-        }
-    }
-
-    // 255, 255, 255, 8
-    // 111, 111, 111, 101
-
-    Ok(n)
-}
-
-/// Read a little-endian 16-bit integer from the input stream.
-#[inline]
-fn read_u16(input: &[u8], input_pos: &mut usize) -> Result<u16, DecompressError> {
-    let dst = input
-        .get(*input_pos..*input_pos + 2)
-        .ok_or(DecompressError::ExpectedAnotherByte)?;
-    *input_pos += 2;
-    Ok(u16::from_le_bytes(dst.try_into().unwrap()))
-}
-
 const FIT_TOKEN_MASK_LITERAL: u8 = 0b00001111;
 const FIT_TOKEN_MASK_MATCH: u8 = 0b11110000;
 
@@ -104,17 +38,85 @@ fn does_token_fit(token: u8) -> bool {
         || (token & FIT_TOKEN_MASK_MATCH) == FIT_TOKEN_MASK_MATCH)
 }
 
+/// The block read by [`decompress_internal`]: a slice, or a range of the output buffer when
+/// decompressing in place.
+pub(crate) trait BlockInput {
+    /// Returns the length of the block.
+    fn len(&self) -> usize;
+
+    /// Returns the block, given the output it is decompressed into.
+    fn bytes<'a>(&'a self, output: &'a impl Sink) -> &'a [u8];
+
+    /// Copies `len` bytes of the block from `pos` to the end of `output`.
+    fn copy_literal(&self, output: &mut impl Sink, pos: usize, len: usize);
+
+    /// Returns the output position of block byte `pos` if the block is stored in the output, which
+    /// limits the output to the input not read yet.
+    #[inline]
+    fn in_place_pos(&self, _pos: usize) -> Option<usize> {
+        None
+    }
+}
+
+impl BlockInput for &[u8] {
+    #[inline]
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+
+    #[inline]
+    fn bytes<'a>(&'a self, _output: &'a impl Sink) -> &'a [u8] {
+        self
+    }
+
+    #[inline]
+    fn copy_literal(&self, output: &mut impl Sink, pos: usize, len: usize) {
+        output.extend_from_slice(&self[pos..pos + len]);
+    }
+}
+
+#[cfg(any(feature = "block", test))]
+impl BlockInput for InPlaceInput {
+    #[inline]
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    #[inline]
+    fn bytes<'a>(&'a self, output: &'a impl Sink) -> &'a [u8] {
+        &output.as_slice()[self.start..self.end]
+    }
+
+    #[inline]
+    fn copy_literal(&self, output: &mut impl Sink, pos: usize, len: usize) {
+        // The literal moves towards the start of the buffer, possibly over its own bytes.
+        output.extend_from_within(self.start + pos, len, len);
+    }
+
+    #[inline]
+    fn in_place_pos(&self, pos: usize) -> Option<usize> {
+        Some(self.start + pos)
+    }
+}
+
 /// Decompress all bytes of `input` into `output`.
 ///
-/// Returns the number of bytes written (decompressed) into `output`.
+/// With `PARTIAL`, decoding stops once `output` is full instead of failing with
+/// `OutputTooSmall`, and the input after that point is not read.
+///
+/// Returns the number of bytes written (decompressed) into `output`. Errors report the input
+/// position of the failing sequence's token and the output position at the time of failure.
 #[inline(always)] // (always) necessary to get the best performance in non LTO builds
-pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
-    input: &[u8],
+pub(crate) fn decompress_internal<const USE_DICT: bool, const PARTIAL: bool, S: Sink>(
+    input: impl BlockInput,
     output: &mut S,
     ext_dict: &[u8],
 ) -> Result<usize, DecompressError> {
     let mut input_pos = 0;
     let initial_output_pos = output.pos();
+    if PARTIAL && output.pos() == output.capacity() {
+        return Ok(0);
+    }
 
     let safe_input_pos = input
         .len()
@@ -137,9 +139,15 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
         // subtokens, the higher and the lower.
         // This token contains to 4-bit "fields", a higher and a lower, representing the literals'
         // length and the back reference's length, respectively.
-        let token = *input
-            .get(input_pos)
-            .ok_or(DecompressError::ExpectedAnotherByte)?;
+        let token_pos = input_pos;
+        let token =
+            *input
+                .bytes(output)
+                .get(input_pos)
+                .ok_or(DecompressError::ExpectedAnotherByte {
+                    input_pos: token_pos,
+                    output_pos: output.pos(),
+                })?;
         input_pos += 1;
 
         // Checking for hot-loop.
@@ -149,26 +157,54 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
         // Ideally we want to check for safe output pos like: output.pos() <= safe_output_pos; But
         // that doesn't work when the safe_output_pos is 0 due to saturated_sub. So we use
         // `<` instead of `<=`, which covers that case.
-        if does_token_fit(token) && input_pos <= safe_input_pos && output.pos() < safe_output_pos {
+        //
+        // In place, the 16 byte literal copy and the 18 byte match copy must also end before the
+        // unread input, which they do if the output is 16 bytes behind the literal.
+        if does_token_fit(token)
+            && input_pos <= safe_input_pos
+            && output.pos() < safe_output_pos
+            && input
+                .in_place_pos(input_pos)
+                .map_or(true, |unread_pos| output.pos() + 16 <= unread_pos)
+        {
             let literal_length = (token >> 4) as usize;
 
-            // casting to [u8;u16] doesn't seem to make a difference vs &[u8] (same assembly)
-            let input: &[u8; 16] = input[input_pos..input_pos + 16].try_into().unwrap();
+            // Copied out of the input, which may be stored in the output.
+            let window: [u8; 16] = input.bytes(output)[input_pos..input_pos + 16]
+                .try_into()
+                .unwrap();
 
             // Copy the literal
             // The literal is at max 14 bytes, and the is_safe_distance check assures
             // that we are far away enough from the end so we can safely copy 16 bytes
-            output.extend_from_slice_wild(input, literal_length);
+            output.extend_from_slice_wild(&window, literal_length);
             input_pos += literal_length;
 
-            // clone as we don't want to mutate
-            let offset = read_u16(input, &mut literal_length.clone())? as usize;
+            // clone as we don't want to mutate; the 16 byte window always holds the offset
+            let offset = read_u16(&window, &mut literal_length.clone()).unwrap() as usize;
             input_pos += 2;
 
             let mut match_length = MINMATCH + (token & 0xF) as usize;
+            prof_inc!(FastTokenHits, 1);
+            prof_inc!(LiteralBytes, literal_length);
+            prof_inc!(MatchBytes, match_length);
+            // Offset 0 is invalid, as in the slow path below.
+            if offset == 0 {
+                return Err(DecompressError::OffsetOutOfBounds {
+                    input_pos: token_pos,
+                    output_pos: output.pos(),
+                    offset,
+                });
+            }
 
             if USE_DICT && offset > output.pos() {
-                let copied = copy_from_dict(output, ext_dict, offset, match_length)?;
+                let copied = copy_from_dict(output, ext_dict, offset, match_length).ok_or(
+                    DecompressError::OffsetOutOfBounds {
+                        input_pos: token_pos,
+                        output_pos: output.pos(),
+                        offset,
+                    },
+                )?;
                 if copied == match_length {
                     continue;
                 }
@@ -194,16 +230,22 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
         // Literal Section
         // If the initial value is 15, it is indicated that another byte will be read and added to
         // it
-        let mut literal_length = (token >> 4) as usize;
+        let mut literal_length = read_literal_length(token, input.bytes(output), &mut input_pos)
+            .ok_or(DecompressError::ExpectedAnotherByte {
+                input_pos: token_pos,
+                output_pos: output.pos(),
+            })?;
         if literal_length != 0 {
-            if literal_length == 15 {
-                // The literal_length length took the maximal value, indicating that there is more
-                // than 15 literal_length bytes. We read the extra integer.
-                literal_length += read_integer(input, &mut input_pos)? as usize;
+            if PARTIAL {
+                literal_length = literal_length.min(output.capacity() - output.pos());
             }
 
             if literal_length > input.len() - input_pos {
-                return Err(DecompressError::LiteralOutOfBounds);
+                return Err(DecompressError::LiteralOutOfBounds {
+                    input_pos: token_pos,
+                    output_pos: output.pos(),
+                    literal_length,
+                });
             }
             // could be skipped with unchecked-decode
             if literal_length > output.capacity() - output.pos() {
@@ -212,8 +254,12 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
                     actual: output.capacity(),
                 });
             }
-            output.extend_from_slice(&input[input_pos..input_pos + literal_length]);
+            prof_inc!(LiteralBytes, literal_length);
+            input.copy_literal(output, input_pos, literal_length);
             input_pos += literal_length;
+            if PARTIAL && output.pos() == output.capacity() {
+                break;
+            }
         }
 
         // If the input stream is emptied, we break out of the loop. This is only the case
@@ -222,7 +268,19 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
             break;
         }
 
-        let offset = read_u16(input, &mut input_pos)? as usize;
+        let offset = read_u16(input.bytes(output), &mut input_pos).ok_or(
+            DecompressError::ExpectedAnotherByte {
+                input_pos: token_pos,
+                output_pos: output.pos(),
+            },
+        )? as usize;
+        if offset == 0 {
+            return Err(DecompressError::OffsetOutOfBounds {
+                input_pos: token_pos,
+                output_pos: output.pos(),
+                offset,
+            });
+        }
         // Obtain the initial match length. The match length is the length of the duplicate segment
         // which will later be copied from data previously decompressed into the output buffer. The
         // initial length is derived from the second part of the token (the lower nibble), we read
@@ -231,13 +289,29 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
 
         // The initial match length can maximally be 19. As with the literal length, this indicates
         // that there are more bytes to read.
-        let mut match_length = MINMATCH + (token & 0xF) as usize;
-        if match_length == MINMATCH + 15 {
-            // The match length took the maximal value, indicating that there is more bytes. We
-            // read the extra integer.
-            match_length += read_integer(input, &mut input_pos)? as usize;
+        let mut match_length = read_match_length(token, input.bytes(output), &mut input_pos)
+            .ok_or(DecompressError::ExpectedAnotherByte {
+                input_pos: token_pos,
+                output_pos: output.pos(),
+            })?;
+        if PARTIAL {
+            match_length = match_length.min(output.capacity() - output.pos());
+        }
+        prof_inc!(MatchBytes, match_length);
+
+        // In place, the match must end before the unread input, which also ends the output, and
+        // so must the wild copies.
+        let mut output_end = output.capacity();
+        if let Some(unread_pos) = input.in_place_pos(input_pos) {
+            if output.pos() + match_length > unread_pos {
+                return Err(DecompressError::InPlaceOverlap {
+                    input_pos: token_pos,
+                    output_end: output.pos() + match_length,
+                    unread_pos,
+                });
+            }
+            output_end = unread_pos;
         }
-
         // could be skipped with unchecked-decode
         if output.pos() + match_length > output.capacity() {
             return Err(DecompressError::OutputTooSmall {
@@ -246,8 +320,17 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
             });
         }
         if USE_DICT && offset > output.pos() {
-            let copied = copy_from_dict(output, ext_dict, offset, match_length)?;
+            let copied = copy_from_dict(output, ext_dict, offset, match_length).ok_or(
+                DecompressError::OffsetOutOfBounds {
+                    input_pos: token_pos,
+                    output_pos: output.pos(),
+                    offset,
+                },
+            )?;
             if copied == match_length {
+                if PARTIAL && output.pos() == output.capacity() {
+                    break;
+                }
                 continue;
             }
             // match crosses ext_dict and output, offset is still correct as output_len was
@@ -256,7 +339,16 @@ pub(crate) fn decompress_internal<const USE_DICT: bool, S: Sink>(
         }
         // We now copy from the already decompressed buffer. This allows us for storing duplicates
         // by simply referencing the other location.
-        duplicate_slice(output, offset, match_length)?;
+        duplicate_slice(output, output_end, offset, match_length).ok_or(
+            DecompressError::OffsetOutOfBounds {
+                input_pos: token_pos,
+                output_pos: output.pos(),
+                offset,
+            },
+        )?;
+        if PARTIAL && output.pos() == output.capacity() {
+            break;
+        }
     }
     Ok(output.pos() - initial_output_pos)
 }
@@ -267,47 +359,49 @@ fn copy_from_dict(
     ext_dict: &[u8],
     offset: usize,
     match_length: usize,
-) -> Result<usize, DecompressError> {
+) -> Option<usize> {
+    prof_inc!(CopyFromDictCalls, 1);
     // If we're here we know offset > output.pos
     debug_assert!(offset > output.pos());
     let (dict_offset, did_overflow) = ext_dict.len().overflowing_sub(offset - output.pos());
     if did_overflow {
-        return Err(DecompressError::OffsetOutOfBounds);
+        return None;
     }
     // Can't copy past ext_dict len, the match may cross dict and output
     let dict_match_length = match_length.min(ext_dict.len() - dict_offset);
     let ext_match = &ext_dict[dict_offset..dict_offset + dict_match_length];
     output.extend_from_slice(ext_match);
-    Ok(dict_match_length)
+    Some(dict_match_length)
 }
 
-/// Extends output by self-referential copies
+/// Extends output by self-referential copies, writing no further than `output_end`.
 #[inline(always)] // (always) necessary otherwise compiler fails to inline it
 fn duplicate_slice(
     output: &mut impl Sink,
+    output_end: usize,
     offset: usize,
     match_length: usize,
-) -> Result<(), DecompressError> {
+) -> Option<()> {
     // This function assumes output will fit match_length, it might panic otherwise.
     if match_length > offset {
         duplicate_overlapping_slice(output, offset, match_length)?;
     } else {
         let (start, did_overflow) = output.pos().overflowing_sub(offset);
         if did_overflow {
-            return Err(DecompressError::OffsetOutOfBounds);
+            return None;
         }
 
         match match_length {
-            0..=32 if output.pos() + 32 <= output.capacity() => {
+            0..=32 if output.pos() + 32 <= output_end => {
                 output.extend_from_within(start, 32, match_length)
             }
-            33..=64 if output.pos() + 64 <= output.capacity() => {
+            33..=64 if output.pos() + 64 <= output_end => {
                 output.extend_from_within(start, 64, match_length)
             }
             _ => output.extend_from_within(start, match_length, match_length),
         }
     }
-    Ok(())
+    Some(())
 }
 
 /// self-referential copy for the case data start (end of output - offset) + match_length overlaps
@@ -317,11 +411,11 @@ fn duplicate_overlapping_slice(
     sink: &mut impl Sink,
     offset: usize,
     match_length: usize,
-) -> Result<(), DecompressError> {
+) -> Option<()> {
     // This function assumes output will fit match_length, it might panic otherwise.
     let (start, did_overflow) = sink.pos().overflowing_sub(offset);
     if did_overflow {
-        return Err(DecompressError::OffsetOutOfBounds);
+        return None;
     }
     if offset == 1 {
         let val = sink.byte_at(start);
@@ -329,31 +423,157 @@ fn duplicate_overlapping_slice(
     } else {
         sink.extend_from_within_overlapping(start, match_length);
     }
-    Ok(())
+    Some(())
 }
 
 /// Decompress all bytes of `input` into `output`.
 /// `output` should be preallocated with a size of of the uncompressed data.
 #[inline]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
 pub fn decompress_into(input: &[u8], output: &mut [u8]) -> Result<usize, DecompressError> {
-    decompress_internal::<false, _>(input, &mut SliceSink::new(output, 0), b"")
+    decompress_internal::<false, false, _>(input, &mut SliceSink::new(output, 0), b"")
+}
+
+/// Decompress all bytes of `input` into `output`, which does not need to be initialized.
+/// `output` should be preallocated with a size of of the uncompressed data.
+///
+/// With `safe-decode` the decoder cannot write to uninitialized memory, so `output` is zeroed
+/// first.
+///
+/// Returns the decompressed prefix of `output`.
+#[inline]
+#[cfg(any(feature = "block", test))]
+pub fn decompress_into_uninit<'a>(
+    input: &[u8],
+    output: &'a mut [MaybeUninit<u8>],
+) -> Result<&'a mut [u8], DecompressError> {
+    let output = crate::block::zero_init(output);
+    let decomp_len = decompress_into(input, output)?;
+    Ok(&mut output[..decomp_len])
+}
+
+/// Decompress all bytes of `input` and append them to `vec`, writing at most `max_len` bytes.
+/// The existing contents of `vec` are not visible to the block, so many independent blocks can
+/// be collected into one buffer.
+///
+/// Returns the number of bytes appended. On error `vec` is left unchanged, apart from its
+/// capacity.
+#[inline]
+#[cfg(any(feature = "block", test))]
+pub fn decompress_append(
+    input: &[u8],
+    vec: &mut Vec<u8>,
+    max_len: usize,
+) -> Result<usize, DecompressError> {
+    let old_len = vec.len();
+    vec.resize(old_len + max_len, 0);
+    let result = decompress_into(input, &mut vec[old_len..]);
+    vec.truncate(old_len + result.as_ref().map_or(0, |&decomp_len| decomp_len));
+    result
+}
+
+/// Decompress `input` into `vec` right after `vec[..pos]`, which serves as the prefix for
+/// matches. At most `max_len` bytes are written.
+///
+/// Returns the number of bytes written; `vec.len()` is at least `pos` plus that afterwards.
+/// The vec is never shortened.
+#[allow(dead_code)]
+#[inline]
+pub(crate) fn decompress_into_vec(
+    input: &[u8],
+    vec: &mut Vec<u8>,
+    pos: usize,
+    max_len: usize,
+) -> Result<usize, DecompressError> {
+    let old_len = vec.len();
+    assert!(pos <= old_len);
+    if old_len < pos + max_len {
+        vec.resize(pos + max_len, 0);
+    }
+    let result = decompress_internal::<false, false, _>(
+        input,
+        &mut SliceSink::new(&mut vec[..pos + max_len], pos),
+        b"",
+    );
+    vec.truncate(old_len.max(pos + result.as_ref().map_or(0, |&decomp_len| decomp_len)));
+    result
+}
+
+/// Decompress the first `target_len` bytes of `input` into `output`.
+///
+/// Decoding stops as soon as `target_len` bytes are produced, so `input` may also be a truncated
+/// block as long as it covers them. `target_len` is capped at `output.len()`, and bytes of
+/// `output` past it are left untouched.
+///
+/// Returns the number of bytes written, which is less than `target_len` only if the whole block
+/// decompresses to fewer bytes.
+#[inline]
+#[cfg(any(feature = "block", test))]
+pub fn decompress_partial_into(
+    input: &[u8],
+    output: &mut [u8],
+    target_len: usize,
+) -> Result<usize, DecompressError> {
+    let target_len = target_len.min(output.len());
+    decompress_internal::<false, true, _>(
+        input,
+        &mut SliceSink::new(&mut output[..target_len], 0),
+        b"",
+    )
 }
 
 /// Decompress all bytes of `input` into `output`.
 ///
 /// Returns the number of bytes written (decompressed) into `output`.
 #[inline]
+#[cfg(any(feature = "block", test))]
 pub fn decompress_into_with_dict(
     input: &[u8],
     output: &mut [u8],
     ext_dict: &[u8],
 ) -> Result<usize, DecompressError> {
-    decompress_internal::<true, _>(input, &mut SliceSink::new(output, 0), ext_dict)
+    decompress_internal::<true, false, _>(input, &mut SliceSink::new(output, 0), ext_dict)
+}
+
+/// Decompress all bytes of `input` into `output[out_start..]`, letting matches reference
+/// `output[..out_start]`.
+///
+/// This is prefix mode: the data the block was compressed against already sits right before the
+/// output position in the same buffer, as for linked blocks in a frame, so no separate `ext_dict`
+/// slice is needed. Blocks compressed with [`compress_with_dict`](super::compress_with_dict)
+/// decode this way when the dictionary is placed in front of the output.
+///
+/// Returns the number of bytes written after `out_start`.
+///
+/// # Panics
+/// Panics if `out_start` is greater than `output.len()`.
+///
+/// # Example
+#[cfg_attr(feature = "block", doc = "```")]
+#[cfg_attr(not(feature = "block"), doc = "```ignore")]
+/// use lz4_flex_wasm_simd::block::{compress_with_dict, decompress_into_with_prefix};
+/// let (first, second) = (b"chunk one of a column", b"chunk two of a column");
+/// let block = compress_with_dict(second, first);
+///
+/// let mut output = vec![0u8; first.len() + second.len()];
+/// output[..first.len()].copy_from_slice(first);
+/// let len = decompress_into_with_prefix(&block, &mut output, first.len()).unwrap();
+/// assert_eq!(&output[first.len()..first.len() + len], second);
+/// ```
+#[inline]
+#[cfg(any(feature = "block", test))]
+pub fn decompress_into_with_prefix(
+    input: &[u8],
+    output: &mut [u8],
+    out_start: usize,
+) -> Result<usize, DecompressError> {
+    decompress_internal::<false, false, _>(input, &mut SliceSink::new(output, out_start), b"")
 }
 
 /// Decompress all bytes of `input` into a new vec. The first 4 bytes are the uncompressed size in
 /// little endian. Can be used in conjunction with `compress_prepend_size`
 #[inline]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
 pub fn decompress_size_prepended(input: &[u8]) -> Result<Vec<u8>, DecompressError> {
     let (uncompressed_size, input) = super::uncompressed_size(input)?;
     decompress(input, uncompressed_size)
@@ -366,10 +586,14 @@ pub fn decompress_size_prepended(input: &[u8]) -> Result<Vec<u8>, DecompressErro
 /// May panic if the parameter `min_uncompressed_size` is smaller than the
 /// uncompressed data.
 #[inline]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
 pub fn decompress(input: &[u8], min_uncompressed_size: usize) -> Result<Vec<u8>, DecompressError> {
     let mut decompressed: Vec<u8> = vec![0; min_uncompressed_size];
-    let decomp_len =
-        decompress_internal::<false, _>(input, &mut SliceSink::new(&mut decompressed, 0), b"")?;
+    let decomp_len = decompress_internal::<false, false, _>(
+        input,
+        &mut SliceSink::new(&mut decompressed, 0),
+        b"",
+    )?;
     decompressed.truncate(decomp_len);
     Ok(decompressed)
 }
@@ -377,6 +601,7 @@ pub fn decompress(input: &[u8], min_uncompressed_size: usize) -> Result<Vec<u8>,
 /// Decompress all bytes of `input` into a new vec. The first 4 bytes are the uncompressed size in
 /// little endian. Can be used in conjunction with `compress_prepend_size_with_dict`
 #[inline]
+#[cfg(any(feature = "block", test))]
 pub fn decompress_size_prepended_with_dict(
     input: &[u8],
     ext_dict: &[u8],
@@ -392,14 +617,18 @@ pub fn decompress_size_prepended_with_dict(
 /// May panic if the parameter `min_uncompressed_size` is smaller than the
 /// uncompressed data.
 #[inline]
+#[cfg(any(feature = "block", test))]
 pub fn decompress_with_dict(
     input: &[u8],
     min_uncompressed_size: usize,
     ext_dict: &[u8],
 ) -> Result<Vec<u8>, DecompressError> {
     let mut decompressed: Vec<u8> = vec![0; min_uncompressed_size];
-    let decomp_len =
-        decompress_internal::<true, _>(input, &mut SliceSink::new(&mut decompressed, 0), ext_dict)?;
+    let decomp_len = decompress_internal::<true, false, _>(
+        input,
+        &mut SliceSink::new(&mut decompressed, 0),
+        ext_dict,
+    )?;
     decompressed.truncate(decomp_len);
     Ok(decompressed)
 }
@@ -420,4 +649,57 @@ mod test {
         decompress(&[0x10, b'a', 2, 0], 4).unwrap_err();
         decompress(&[0x40, b'a', 1, 0], 4).unwrap_err();
     }
+
+    fn encode_len(mut len: usize) -> (u8, Vec<u8>) {
+        if len < 15 {
+            return (len as u8, Vec::new());
+        }
+        len -= 15;
+        let mut extras = vec![255; len / 255];
+        extras.push((len % 255) as u8);
+        (15, extras)
+    }
+
+    /// Exercises the overlapping match copy, including the SIMD pattern path on wasm.
+    #[test]
+    fn overlap_offsets_1_to_40() {
+        for offset in 1usize..=40 {
+            let literals: Vec<u8> = (0..offset).map(|i| b'a' + (i as u8 % 26)).collect();
+            let match_length = 96usize;
+            let (lit_nibble, lit_extra) = encode_len(literals.len());
+            let (match_nibble, match_extra) = encode_len(match_length - MINMATCH);
+            let mut compressed = vec![(lit_nibble << 4) | match_nibble];
+            compressed.extend_from_slice(&lit_extra);
+            compressed.extend_from_slice(&literals);
+            compressed.extend_from_slice(&(offset as u16).to_le_bytes());
+            compressed.extend_from_slice(&match_extra);
+            compressed.push(0);
+
+            let mut expected = literals.clone();
+            for _ in 0..match_length {
+                expected.push(expected[expected.len() - offset]);
+            }
+            let decoded = decompress(&compressed, expected.len()).unwrap();
+            assert_eq!(decoded, expected, "offset={offset}");
+        }
+    }
+
+    #[test]
+    fn decompress_into_vec_keeps_longer_vec() {
+        let prefix = b"the quick brown fox ";
+        let data = b"the quick brown fox jumps over the lazy dog, the quick brown fox";
+        let compressed = crate::block::compress_with_dict(data, prefix);
+
+        let mut vec = prefix.to_vec();
+        vec.resize(200, 0xAA);
+        let len = decompress_into_vec(&compressed, &mut vec, prefix.len(), data.len()).unwrap();
+        assert_eq!(len, data.len());
+        assert_eq!(vec.len(), 200);
+        assert_eq!(&vec[prefix.len()..prefix.len() + len], data);
+
+        let mut vec = prefix.to_vec();
+        let len = decompress_into_vec(&compressed, &mut vec, prefix.len(), data.len()).unwrap();
+        assert_eq!(vec.len(), prefix.len() + len);
+        assert_eq!(&vec[prefix.len()..], data);
+    }
 }
@@ -16,6 +16,12 @@ use alloc::boxed::Box;
 ///
 /// hashes and right shifts to a maximum value of 16bit, 65535
 /// The right shift is done in order to not exceed, the hashtables capacity
+#[cfg(any(
+    feature = "block",
+    feature = "wasm-exports",
+    test,
+    target_pointer_width = "32"
+))]
 #[inline]
 fn hash(sequence: u32) -> u32 {
     (sequence.wrapping_mul(2654435761_u32)) >> 16
@@ -51,35 +57,97 @@ pub trait HashTable {
     }
 }
 
-const HASHTABLE_SIZE_4K: usize = 4 * 1024;
-const HASHTABLE_BIT_SHIFT_4K: usize = 4;
+/// Smallest supported `MEMORY_USAGE`: 256 entries.
+pub(crate) const MIN_MEMORY_USAGE: u32 = 10;
+/// Largest supported `MEMORY_USAGE`: one entry per 16 bit hash value.
+pub(crate) const MAX_MEMORY_USAGE: u32 = 18;
 
+/// Number of entries and hash shift of a table taking `2^MEMORY_USAGE` bytes with `u32` entries,
+/// as `LZ4_MEMORY_USAGE` in the reference implementation.
+struct TableSize<const MEMORY_USAGE: u32>;
+impl<const MEMORY_USAGE: u32> TableSize<MEMORY_USAGE> {
+    const LEN: usize = {
+        assert!(
+            MEMORY_USAGE >= MIN_MEMORY_USAGE && MEMORY_USAGE <= MAX_MEMORY_USAGE,
+            "MEMORY_USAGE must be between 10 and 18"
+        );
+        1 << (MEMORY_USAGE - 2)
+    };
+    /// The hashes are 16 bits wide, and keep their upper bits.
+    const BIT_SHIFT: usize = 16 - Self::LEN.trailing_zeros() as usize;
+
+    /// Index into a table of `LEN` entries. The mask keeps it in bounds for any `hash`.
+    #[inline]
+    fn index(hash: usize) -> usize {
+        (hash >> Self::BIT_SHIFT) & (Self::LEN - 1)
+    }
+}
+
+/// Reads entry `index`, which `TableSize::index` keeps below the length of `dict`.
+#[inline]
+fn load<T: Copy>(dict: &[T], index: usize) -> T {
+    #[cfg(feature = "safe-encode")]
+    {
+        dict[index]
+    }
+    #[cfg(not(feature = "safe-encode"))]
+    {
+        debug_assert!(index < dict.len());
+        // SAFETY: tables are allocated with `TableSize::LEN` entries and indexed through
+        // `TableSize::index`.
+        unsafe { *dict.get_unchecked(index) }
+    }
+}
+
+/// Writes entry `index`, which `TableSize::index` keeps below the length of `dict`.
+#[inline]
+fn store<T>(dict: &mut [T], index: usize, val: T) {
+    #[cfg(feature = "safe-encode")]
+    {
+        dict[index] = val;
+    }
+    #[cfg(not(feature = "safe-encode"))]
+    {
+        debug_assert!(index < dict.len());
+        // SAFETY: see `load`.
+        unsafe { *dict.get_unchecked_mut(index) = val }
+    }
+}
+
+/// Hash table with `u16` positions, for inputs shorter than 64KB. It has as many entries as
+/// [`HashTableU32`] with the same `MEMORY_USAGE`, in half the memory.
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
 #[derive(Debug)]
 #[repr(align(64))]
-pub struct HashTable4KU16 {
-    dict: Box<[u16; HASHTABLE_SIZE_4K]>,
+pub struct HashTableU16<const MEMORY_USAGE: u32> {
+    dict: Box<[u16]>,
 }
-impl HashTable4KU16 {
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+impl<const MEMORY_USAGE: u32> HashTableU16<MEMORY_USAGE> {
+    /// Number of entries.
+    pub const LEN: usize = TableSize::<MEMORY_USAGE>::LEN;
+
     #[inline]
     pub fn new() -> Self {
         // This generates more efficient assembly in contrast to Box::new(slice), because of an
         // optimized call alloc_zeroed, vs. alloc + memset
-        // try_into is optimized away
-        let dict = alloc::vec![0; HASHTABLE_SIZE_4K]
-            .into_boxed_slice()
-            .try_into()
-            .unwrap();
+        let dict = alloc::vec![0; TableSize::<MEMORY_USAGE>::LEN].into_boxed_slice();
         Self { dict }
     }
 }
-impl HashTable for HashTable4KU16 {
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+impl<const MEMORY_USAGE: u32> HashTable for HashTableU16<MEMORY_USAGE> {
     #[inline]
     fn get_at(&self, hash: usize) -> usize {
-        self.dict[hash >> HASHTABLE_BIT_SHIFT_4K] as usize
+        load(&self.dict, TableSize::<MEMORY_USAGE>::index(hash)) as usize
     }
     #[inline]
     fn put_at(&mut self, hash: usize, val: usize) {
-        self.dict[hash >> HASHTABLE_BIT_SHIFT_4K] = val as u16;
+        store(
+            &mut self.dict,
+            TableSize::<MEMORY_USAGE>::index(hash),
+            val as u16,
+        );
     }
     #[inline]
     fn clear(&mut self) {
@@ -91,17 +159,15 @@ impl HashTable for HashTable4KU16 {
     }
 }
 
+/// Hash table with `u32` positions, taking `2^MEMORY_USAGE` bytes.
 #[derive(Debug)]
-pub struct HashTable4K {
-    dict: Box<[u32; HASHTABLE_SIZE_4K]>,
+pub struct HashTableU32<const MEMORY_USAGE: u32> {
+    dict: Box<[u32]>,
 }
-impl HashTable4K {
+impl<const MEMORY_USAGE: u32> HashTableU32<MEMORY_USAGE> {
     #[inline]
     pub fn new() -> Self {
-        let dict = alloc::vec![0; HASHTABLE_SIZE_4K]
-            .into_boxed_slice()
-            .try_into()
-            .unwrap();
+        let dict = alloc::vec![0; TableSize::<MEMORY_USAGE>::LEN].into_boxed_slice();
         Self { dict }
     }
 
@@ -113,14 +179,18 @@ impl HashTable4K {
         }
     }
 }
-impl HashTable for HashTable4K {
+impl<const MEMORY_USAGE: u32> HashTable for HashTableU32<MEMORY_USAGE> {
     #[inline]
     fn get_at(&self, hash: usize) -> usize {
-        self.dict[hash >> HASHTABLE_BIT_SHIFT_4K] as usize
+        load(&self.dict, TableSize::<MEMORY_USAGE>::index(hash)) as usize
     }
     #[inline]
     fn put_at(&mut self, hash: usize, val: usize) {
-        self.dict[hash >> HASHTABLE_BIT_SHIFT_4K] = val as u32;
+        store(
+            &mut self.dict,
+            TableSize::<MEMORY_USAGE>::index(hash),
+            val as u32,
+        );
     }
     #[inline]
     fn clear(&mut self) {
@@ -128,36 +198,47 @@ impl HashTable for HashTable4K {
     }
 }
 
-const HASHTABLE_SIZE_8K: usize = 8 * 1024;
-const HASH_TABLE_BIT_SHIFT_8K: usize = 3;
-
+/// Hash table with `LEN` `u16` positions held inline, so small inputs can use a table on the
+/// stack sized to the input. `LEN` must be a power of two, at most 65536. It hashes and indexes
+/// like a [`HashTableU16`] with `LEN` entries.
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
 #[derive(Debug)]
-pub struct HashTable8K {
-    dict: Box<[u32; HASHTABLE_SIZE_8K]>,
+pub struct HashTableU16Stack<const LEN: usize> {
+    dict: [u16; LEN],
 }
-#[allow(dead_code)]
-impl HashTable8K {
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+impl<const LEN: usize> HashTableU16Stack<LEN> {
+    const BIT_SHIFT: usize = {
+        assert!(LEN.is_power_of_two() && LEN <= 1 << 16);
+        16 - LEN.trailing_zeros() as usize
+    };
+
     #[inline]
     pub fn new() -> Self {
-        let dict = alloc::vec![0; HASHTABLE_SIZE_8K]
-            .into_boxed_slice()
-            .try_into()
-            .unwrap();
+        Self { dict: [0; LEN] }
+    }
 
-        Self { dict }
+    #[inline]
+    fn index(hash: usize) -> usize {
+        (hash >> Self::BIT_SHIFT) & (LEN - 1)
     }
 }
-impl HashTable for HashTable8K {
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
+impl<const LEN: usize> HashTable for HashTableU16Stack<LEN> {
     #[inline]
     fn get_at(&self, hash: usize) -> usize {
-        self.dict[hash >> HASH_TABLE_BIT_SHIFT_8K] as usize
+        load(&self.dict, Self::index(hash)) as usize
     }
     #[inline]
     fn put_at(&mut self, hash: usize, val: usize) {
-        self.dict[hash >> HASH_TABLE_BIT_SHIFT_8K] = val as u32;
+        store(&mut self.dict, Self::index(hash), val as u16);
     }
     #[inline]
     fn clear(&mut self) {
         self.dict.fill(0);
     }
+    #[inline]
+    fn get_hash_at(input: &[u8], pos: usize) -> usize {
+        hash(super::get_batch(input, pos)) as usize
+    }
 }
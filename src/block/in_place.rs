@@ -0,0 +1,166 @@
+//! Decompressing a block within the buffer that holds it.
+
+use core::ops::Range;
+
+use crate::block::decompress::decompress_internal;
+use crate::block::DecompressError;
+use crate::sink::SliceSink;
+
+/// A block stored at `start..end` of the buffer it is decompressed into.
+pub(crate) struct InPlaceInput {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+/// Returns the room needed after the decompressed data to decompress a block of
+/// `compressed_len` bytes in place, like `LZ4_DECOMPRESS_INPLACE_MARGIN` in the reference
+/// implementation.
+///
+/// A buffer of `decompressed_len + decompress_in_place_margin(compressed_len)` bytes, with the
+/// block copied to its end, is large enough for [`decompress_in_place`].
+#[inline]
+pub const fn decompress_in_place_margin(compressed_len: usize) -> usize {
+    (compressed_len >> 8) + 32
+}
+
+/// Decompresses the block in `buf[compressed]` into the start of `buf`, and returns the
+/// decompressed length.
+///
+/// This avoids a separate output buffer: place the block at the end of a buffer sized with
+/// [`decompress_in_place_margin`]. The output grows towards the compressed bytes, and decoding
+/// fails with [`DecompressError::InPlaceOverlap`] instead of overwriting bytes it has not read
+/// yet. It uses the same decoder as [`decompress_into`](crate::block::decompress_into), with or
+/// without `safe-decode`.
+///
+/// # Panics
+/// Panics if `compressed` is out of bounds of `buf`.
+///
+/// # Example
+#[cfg_attr(feature = "block", doc = "```")]
+#[cfg_attr(not(feature = "block"), doc = "```ignore")]
+/// use lz4_flex_wasm_simd::block::{compress, decompress_in_place, decompress_in_place_margin};
+/// let input = b"decompressed where it was stored, decompressed where it was stored";
+/// let compressed = compress(input);
+///
+/// let mut buf = vec![0u8; input.len() + decompress_in_place_margin(compressed.len())];
+/// let (start, end) = (buf.len() - compressed.len(), buf.len());
+/// buf[start..].copy_from_slice(&compressed);
+/// let len = decompress_in_place(&mut buf, start..end).unwrap();
+/// assert_eq!(&buf[..len], input);
+/// ```
+pub fn decompress_in_place(
+    buf: &mut [u8],
+    compressed: Range<usize>,
+) -> Result<usize, DecompressError> {
+    let Range { start, end } = compressed;
+    assert!(
+        start <= end && end <= buf.len(),
+        "compressed range out of bounds"
+    );
+    decompress_internal::<false, false, _>(
+        InPlaceInput { start, end },
+        &mut SliceSink::new(buf, 0),
+        b"",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn in_place(compressed: &[u8], decompressed_len: usize) -> Result<Vec<u8>, DecompressError> {
+        let mut buf = vec![0u8; decompressed_len + decompress_in_place_margin(compressed.len())];
+        let (start, end) = (buf.len() - compressed.len(), buf.len());
+        buf[start..].copy_from_slice(compressed);
+        let len = decompress_in_place(&mut buf, start..end)?;
+        buf.truncate(len);
+        Ok(buf)
+    }
+
+    #[test]
+    fn roundtrips_with_margin() {
+        let noise: Vec<u8> = (0..100_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        let runs: Vec<u8> = (0..100_000u32).map(|i| (i / 5000) as u8).collect();
+        let periodic: Vec<u8> = (0..20_000u32).map(|i| b"abc"[i as usize % 3]).collect();
+        for input in [&b""[..], b"a", &noise, &runs, &periodic, &noise[..300]] {
+            let compressed = crate::block::compress(input);
+            assert_eq!(in_place(&compressed, input.len()).unwrap(), input);
+        }
+    }
+
+    #[test]
+    fn refuses_to_overwrite_unread_input() {
+        // "a" followed by a 40 byte match at offset 1, with no room in front of the block.
+        let mut buf = vec![0x1F, b'a', 1, 0, 21, 0x10, b'z'];
+        let len = buf.len();
+        let result = decompress_in_place(&mut buf, 0..len);
+        assert!(
+            matches!(
+                result,
+                Err(DecompressError::InPlaceOverlap {
+                    input_pos: 0,
+                    output_end: 41,
+                    unread_pos: 5
+                })
+            ),
+            "{result:?}"
+        );
+
+        let mut buf = vec![0u8; 64];
+        buf[57..].copy_from_slice(&[0x1F, b'a', 1, 0, 21, 0x10, b'z']);
+        assert_eq!(decompress_in_place(&mut buf, 57..64).unwrap(), 42);
+        assert!(buf[..41].iter().all(|&b| b == b'a'));
+        assert_eq!(buf[41], b'z');
+    }
+
+    #[test]
+    fn short_margin_never_decodes_garbage() {
+        // Long literals move over their own bytes, and long matches leave the output too close to
+        // the input for the wild copies.
+        let text = include_bytes!("../../bench-data/text_50kb.txt");
+        let noise: Vec<u8> = (0..5000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        let mut input = Vec::new();
+        for i in 0..20 {
+            input.extend_from_slice(&noise[i * 200..i * 200 + 37 * i]);
+            input.extend_from_slice(&text[..300 + 50 * i]);
+        }
+        let compressed = crate::block::compress(&input);
+        let margin = decompress_in_place_margin(compressed.len());
+        let mut failed = 0;
+        for room in 0..=margin + 16 {
+            let mut buf = vec![0u8; input.len() + room];
+            let (start, end) = (buf.len() - compressed.len(), buf.len());
+            buf[start..].copy_from_slice(&compressed);
+            match decompress_in_place(&mut buf, start..end) {
+                Ok(len) => assert_eq!(&buf[..len], &input[..]),
+                Err(DecompressError::InPlaceOverlap { .. }) => failed += 1,
+                Err(err) => panic!("{err}"),
+            }
+        }
+        assert!(failed > 0 && failed <= margin);
+    }
+
+    #[test]
+    fn malformed() {
+        let mut buf = [0u8; 16];
+        assert!(matches!(
+            decompress_in_place(&mut buf, 16..16),
+            Err(DecompressError::ExpectedAnotherByte { .. })
+        ));
+        buf[11..].copy_from_slice(&[0x10, b'a', 2, 0, 0x00]);
+        assert!(matches!(
+            decompress_in_place(&mut buf, 11..16),
+            Err(DecompressError::OffsetOutOfBounds { .. })
+        ));
+        buf[12..].copy_from_slice(&[0x30, b'a', b'b', b'c']);
+        assert!(matches!(
+            decompress_in_place(&mut buf, 12..15),
+            Err(DecompressError::LiteralOutOfBounds { .. })
+        ));
+    }
+}
@@ -0,0 +1,177 @@
+//! Walking the sequences of a compressed block without decompressing it.
+
+use crate::block::sequence::next_sequence;
+use crate::block::DecompressError;
+
+/// Returns the decompressed size of a block by walking its token and length fields, without
+/// copying any data. Useful to size the output exactly for blocks stored without a size prefix.
+///
+/// Match offsets are not checked, so a successful result does not mean the block decompresses;
+/// the decoders still validate it.
+///
+/// # Example
+#[cfg_attr(feature = "block", doc = "```")]
+#[cfg_attr(not(feature = "block"), doc = "```ignore")]
+/// use lz4_flex_wasm_simd::block::{compress, decompressed_len};
+/// let compressed = compress(b"a block without a size prefix, a block without a size prefix");
+/// assert_eq!(decompressed_len(&compressed).unwrap(), 60);
+/// ```
+pub fn decompressed_len(input: &[u8]) -> Result<usize, DecompressError> {
+    if input.is_empty() {
+        return Err(DecompressError::ExpectedAnotherByte {
+            input_pos: 0,
+            output_pos: 0,
+        });
+    }
+    let mut input_pos = 0;
+    let mut len: usize = 0;
+    while let Some(sequence) = next_sequence(input, &mut input_pos, len)? {
+        len = len
+            .saturating_add(sequence.literal_length)
+            .saturating_add(sequence.match_length);
+    }
+    Ok(len)
+}
+
+/// Checks that `input` is a well-formed block that decompresses to exactly `expected_len`
+/// bytes, without writing any output.
+///
+/// Literal runs must lie within the input, and every match offset must be non-zero and point
+/// into the output produced so far or into `dict`, as for
+/// [`decompress_into_with_dict`](super::decompress_into_with_dict). Pass an empty `dict` for
+/// independent blocks.
+///
+/// # Example
+#[cfg_attr(feature = "block", doc = "```")]
+#[cfg_attr(not(feature = "block"), doc = "```ignore")]
+/// use lz4_flex_wasm_simd::block::{compress, validate};
+/// let input = b"checked before it is stored, checked before it is stored";
+/// let compressed = compress(input);
+/// assert!(validate(&compressed, input.len(), b"").is_ok());
+/// assert!(validate(&compressed[..compressed.len() - 1], input.len(), b"").is_err());
+/// ```
+pub fn validate(input: &[u8], expected_len: usize, dict: &[u8]) -> Result<(), DecompressError> {
+    if input.is_empty() {
+        return Err(DecompressError::ExpectedAnotherByte {
+            input_pos: 0,
+            output_pos: 0,
+        });
+    }
+    let mut input_pos = 0;
+    let mut output_len: usize = 0;
+    loop {
+        let token_pos = input_pos;
+        let Some(sequence) = next_sequence(input, &mut input_pos, output_len)? else {
+            break;
+        };
+        output_len = output_len.saturating_add(sequence.literal_length);
+        if sequence.match_length != 0 {
+            if sequence.offset == 0 || sequence.offset > output_len + dict.len() {
+                return Err(DecompressError::OffsetOutOfBounds {
+                    input_pos: token_pos,
+                    output_pos: output_len,
+                    offset: sequence.offset,
+                });
+            }
+            output_len = output_len.saturating_add(sequence.match_length);
+        }
+        if output_len > expected_len {
+            return Err(DecompressError::OutputTooSmall {
+                expected: output_len,
+                actual: expected_len,
+            });
+        }
+    }
+    if output_len != expected_len {
+        return Err(DecompressError::UncompressedSizeDiffers {
+            expected: expected_len,
+            actual: output_len,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn matches_compressed_inputs() {
+        for len in [0usize, 1, 12, 13, 100, 4096, 70_000] {
+            let input: Vec<u8> = (0..len).map(|i| (i % 251) as u8 ^ (i / 7) as u8).collect();
+            let compressed = crate::block::compress(&input);
+            assert_eq!(decompressed_len(&compressed).unwrap(), len, "len={len}");
+        }
+    }
+
+    #[test]
+    fn long_lengths() {
+        // 15 + 255 + 10 literals, then a 4 + 15 + 255 + 1 byte match at offset 1.
+        let mut block = vec![0xFF, 255, 10];
+        block.extend(core::iter::repeat(b'a').take(280));
+        block.extend_from_slice(&[1, 0, 255, 1]);
+        block.push(0x10);
+        block.push(b'z');
+        assert_eq!(decompressed_len(&block).unwrap(), 280 + 275 + 1);
+        assert_eq!(
+            crate::block::decompress(&block, 556).unwrap().len(),
+            decompressed_len(&block).unwrap()
+        );
+    }
+
+    #[test]
+    fn validate_checks_offsets_and_length() {
+        let input: Vec<u8> = (0..5000u32).map(|i| (i % 97) as u8).collect();
+        let compressed = crate::block::compress(&input);
+        validate(&compressed, input.len(), b"").unwrap();
+        assert!(matches!(
+            validate(&compressed, input.len() + 1, b""),
+            Err(DecompressError::UncompressedSizeDiffers { .. })
+        ));
+        assert!(matches!(
+            validate(&compressed, input.len() - 1, b""),
+            Err(DecompressError::OutputTooSmall { .. })
+        ));
+
+        // Offset 2 with one byte of output needs one byte of dictionary.
+        let block = [0x10, b'a', 2, 0, 0x00];
+        assert!(matches!(
+            validate(&block, 5, b""),
+            Err(DecompressError::OffsetOutOfBounds { .. })
+        ));
+        validate(&block, 5, b"d").unwrap();
+        // Offset 0 is never valid.
+        assert!(matches!(
+            validate(&[0x10, b'a', 0, 0, 0x00], 5, b"d"),
+            Err(DecompressError::OffsetOutOfBounds { .. })
+        ));
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(matches!(
+            decompressed_len(&[]),
+            Err(DecompressError::ExpectedAnotherByte { .. })
+        ));
+        assert!(matches!(
+            decompressed_len(&[0x30, b'a']),
+            Err(DecompressError::LiteralOutOfBounds { .. })
+        ));
+        // Offset cut short.
+        assert!(matches!(
+            decompressed_len(&[0x10, b'a', 1]),
+            Err(DecompressError::ExpectedAnotherByte { .. })
+        ));
+        // Match length continuation missing.
+        assert!(matches!(
+            decompressed_len(&[0x1F, b'a', 1, 0]),
+            Err(DecompressError::ExpectedAnotherByte { .. })
+        ));
+        // A match must be followed by a last sequence.
+        assert!(matches!(
+            decompressed_len(&[0x10, b'a', 1, 0]),
+            Err(DecompressError::ExpectedAnotherByte { .. })
+        ));
+    }
+}
@@ -17,7 +17,21 @@
 
 #[cfg_attr(feature = "safe-encode", forbid(unsafe_code))]
 pub(crate) mod compress;
+#[cfg_attr(feature = "safe-encode", forbid(unsafe_code))]
 pub(crate) mod hashtable;
+#[cfg(any(feature = "block", test))]
+#[forbid(unsafe_code)]
+pub(crate) mod in_place;
+#[cfg(any(feature = "block", test))]
+#[forbid(unsafe_code)]
+pub(crate) mod inspect;
+#[forbid(unsafe_code)]
+pub(crate) mod profile;
+#[cfg(any(feature = "block", feature = "safe-decode", test))]
+#[forbid(unsafe_code)]
+pub(crate) mod sequence;
+#[forbid(unsafe_code)]
+pub(crate) mod stats;
 
 #[cfg(feature = "safe-decode")]
 #[cfg_attr(feature = "safe-decode", forbid(unsafe_code))]
@@ -32,7 +46,30 @@ pub(crate) mod decompress;
 pub use compress::*;
 #[allow(unused_imports)]
 pub use decompress::*;
+#[cfg(any(feature = "block", test))]
+#[allow(unused_imports)]
+pub use in_place::{decompress_in_place, decompress_in_place_margin};
+#[cfg(any(feature = "block", test))]
+#[allow(unused_imports)]
+pub use inspect::{decompressed_len, validate};
+#[cfg(feature = "decompress-prof")]
+#[allow(unused_imports)]
+pub use profile::{read_decompress_profile, reset_decompress_profile, DecompressProfileSnapshot};
+#[cfg(all(feature = "decompress-prof", feature = "std"))]
+#[allow(unused_imports)]
+pub use profile::{read_thread_decompress_profile, reset_thread_decompress_profile};
+#[allow(unused_imports)]
+pub use stats::{CompressStats, HISTOGRAM_BUCKETS};
 
+#[cfg(any(
+    feature = "block",
+    all(
+        feature = "wasm-exports",
+        any(not(feature = "safe-decode"), not(feature = "safe-encode"))
+    ),
+    test
+))]
+use core::mem::MaybeUninit;
 use core::{error::Error, fmt};
 
 pub(crate) const WINDOW_SIZE: usize = 64 * 1024;
@@ -91,11 +128,46 @@ pub enum DecompressError {
         actual: usize,
     },
     /// Literal is out of bounds of the input
-    LiteralOutOfBounds,
+    LiteralOutOfBounds {
+        /// Input position of the token of the failing sequence
+        input_pos: usize,
+        /// Number of bytes decompressed before the failing sequence
+        output_pos: usize,
+        /// Length of the literal run
+        literal_length: usize,
+    },
     /// Expected another byte, but none found.
-    ExpectedAnotherByte,
+    ExpectedAnotherByte {
+        /// Input position of the token of the failing sequence
+        input_pos: usize,
+        /// Number of bytes decompressed before the failing sequence
+        output_pos: usize,
+    },
     /// Deduplication offset out of bounds (not in buffer).
-    OffsetOutOfBounds,
+    OffsetOutOfBounds {
+        /// Input position of the token of the failing sequence
+        input_pos: usize,
+        /// Number of bytes decompressed when the match was reached
+        output_pos: usize,
+        /// The match offset
+        offset: usize,
+    },
+    /// The block decompresses to fewer bytes than expected.
+    UncompressedSizeDiffers {
+        /// Expected decompressed size
+        expected: usize,
+        /// Actual decompressed size
+        actual: usize,
+    },
+    /// Decompressing in place would overwrite input that has not been read yet.
+    InPlaceOverlap {
+        /// Input position of the token of the failing sequence
+        input_pos: usize,
+        /// Output length the sequence needs
+        output_end: usize,
+        /// Buffer position of the first unread input byte
+        unread_pos: usize,
+    },
 }
 
 #[derive(Debug)]
@@ -116,15 +188,47 @@ impl fmt::Display for DecompressError {
                      {expected}"
                 )
             }
-            DecompressError::LiteralOutOfBounds => {
-                f.write_str("literal is out of bounds of the input")
-            }
-            DecompressError::ExpectedAnotherByte => {
-                f.write_str("expected another byte, found none")
-            }
-            DecompressError::OffsetOutOfBounds => {
-                f.write_str("the offset to copy is not contained in the decompressed buffer")
+            DecompressError::LiteralOutOfBounds {
+                input_pos,
+                output_pos,
+                literal_length,
+            } => write!(
+                f,
+                "literal of length {literal_length} is out of bounds of the input (sequence at \
+                 input {input_pos}, output {output_pos})"
+            ),
+            DecompressError::ExpectedAnotherByte {
+                input_pos,
+                output_pos,
+            } => write!(
+                f,
+                "expected another byte, found none (sequence at input {input_pos}, output \
+                 {output_pos})"
+            ),
+            DecompressError::OffsetOutOfBounds {
+                input_pos,
+                output_pos,
+                offset,
+            } => write!(
+                f,
+                "the offset {offset} to copy is not contained in the decompressed buffer \
+                 (sequence at input {input_pos}, output {output_pos})"
+            ),
+            DecompressError::UncompressedSizeDiffers { expected, actual } => {
+                write!(
+                    f,
+                    "the block decompresses to {actual} bytes, expected {expected}"
+                )
             }
+            DecompressError::InPlaceOverlap {
+                input_pos,
+                output_end,
+                unread_pos,
+            } => write!(
+                f,
+                "decompressing in place would overwrite unread input, output up to {output_end} \
+                 passes the unread input at {unread_pos} (sequence at input {input_pos})"
+            ),
         }
     }
 }
@@ -148,14 +252,49 @@ impl Error for CompressError {}
 /// It will read the first 4 bytes as little-endian encoded length, and return
 /// the rest of the bytes after the length encoding.
 #[inline]
+#[cfg(any(feature = "block", feature = "wasm-exports", test))]
 pub fn uncompressed_size(input: &[u8]) -> Result<(usize, &[u8]), DecompressError> {
-    let size = input.get(..4).ok_or(DecompressError::ExpectedAnotherByte)?;
+    let size = input.get(..4).ok_or(DecompressError::ExpectedAnotherByte {
+        input_pos: 0,
+        output_pos: 0,
+    })?;
     let size: &[u8; 4] = size.try_into().unwrap();
     let uncompressed_size = u32::from_le_bytes(*size) as usize;
     let rest = &input[4..];
     Ok((uncompressed_size, rest))
 }
 
+/// Zero-fills `output` and returns it as initialized bytes. Used by the `safe-decode` and
+/// `safe-encode` variants of the `*_uninit` functions, which cannot write to uninitialized memory.
+#[cfg(all(
+    any(feature = "block", test),
+    any(feature = "safe-decode", feature = "safe-encode")
+))]
+#[inline]
+pub(crate) fn zero_init(output: &mut [MaybeUninit<u8>]) -> &mut [u8] {
+    output.fill(MaybeUninit::new(0));
+    // SAFETY: every element was just initialized.
+    unsafe { assume_init_prefix(output, output.len()) }
+}
+
+/// Returns the first `len` elements of `output` as initialized bytes.
+///
+/// # Safety
+/// `output[..len]` must be initialized.
+#[cfg(any(
+    feature = "block",
+    all(
+        feature = "wasm-exports",
+        any(not(feature = "safe-decode"), not(feature = "safe-encode"))
+    ),
+    test
+))]
+#[inline]
+pub(crate) unsafe fn assume_init_prefix(output: &mut [MaybeUninit<u8>], len: usize) -> &mut [u8] {
+    let output = &mut output[..len];
+    core::slice::from_raw_parts_mut(output.as_mut_ptr() as *mut u8, len)
+}
+
 #[test]
 #[cfg(target_pointer_width = "64")] // only relevant for 64bit CPUs
 fn large_integer_roundtrip() {
@@ -167,7 +306,7 @@ fn large_integer_roundtrip() {
     self::compress::write_integer(&mut sink, value);
 
     #[cfg(feature = "safe-decode")]
-    let value_decompressed = self::decompress_safe::read_integer(&buf, &mut 0).unwrap();
+    let value_decompressed = self::sequence::read_integer(&buf, &mut 0).unwrap();
 
     #[cfg(not(feature = "safe-decode"))]
     let value_decompressed = {
@@ -0,0 +1,246 @@
+//! Counters of the block decoder's code paths, collected with the `decompress-prof` feature.
+//!
+//! Each counter is kept twice: once for the whole process, read with
+//! [`read_decompress_profile`], and with `std` once per thread, read with
+//! `read_thread_decompress_profile`. On wasm without the `atomics` target feature there is only
+//! one thread, and both read the same counters. Without `decompress-prof` nothing is counted and
+//! the snapshots stay zero.
+
+use core::ops::AddAssign;
+
+/// Counters of the block decoder, see [`read_decompress_profile`].
+///
+/// The default decoder counts every field. The `safe-decode` decoder only counts
+/// `fast_token_hits`, `copy_from_dict_calls`, `literal_bytes` and `match_bytes`, its match
+/// copies have no separate kernels.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DecompressProfileSnapshot {
+    /// Sequences decoded by the fast path for short literals and matches.
+    pub fast_token_hits: u64,
+    /// Matches copied with a 16-byte wild copy, which may write past their end.
+    pub duplicate_nonoverlap_wild: u64,
+    /// Matches that do not overlap the bytes they produce, but are too close to them or to the
+    /// end of the output for a wild copy.
+    pub duplicate_near_end_exact_nonoverlap: u64,
+    /// Overlapping matches of at least 8 bytes with an offset of 1, 2, 4 or 8, copied with a
+    /// repeated `u64`.
+    pub duplicate_overlap_small_u64: u64,
+    /// Overlapping matches of at least 16 bytes with an offset below 16, copied with a SIMD
+    /// shuffle (wasm SIMD128, x86_64 and aarch64).
+    pub duplicate_overlap_small_simd: u64,
+    /// Overlapping matches with an offset of 16 or more, copied in steps of the offset.
+    pub duplicate_overlap_large_offset_chunk: u64,
+    /// Other overlapping matches, copied byte by byte.
+    pub duplicate_overlap_fallback_byte: u64,
+    /// Matches that start in the external dictionary.
+    pub copy_from_dict_calls: u64,
+    /// Bytes written as literals.
+    pub literal_bytes: u64,
+    /// Bytes written by matches.
+    pub match_bytes: u64,
+}
+
+impl AddAssign<&DecompressProfileSnapshot> for DecompressProfileSnapshot {
+    fn add_assign(&mut self, other: &DecompressProfileSnapshot) {
+        let counters = self.counters_mut();
+        for (a, b) in counters.into_iter().zip(other.counters()) {
+            *a += b;
+        }
+    }
+}
+
+impl DecompressProfileSnapshot {
+    /// The counters in the order of the `Counter` indices.
+    fn counters(&self) -> [u64; COUNTERS] {
+        [
+            self.fast_token_hits,
+            self.duplicate_nonoverlap_wild,
+            self.duplicate_near_end_exact_nonoverlap,
+            self.duplicate_overlap_small_u64,
+            self.duplicate_overlap_small_simd,
+            self.duplicate_overlap_large_offset_chunk,
+            self.duplicate_overlap_fallback_byte,
+            self.copy_from_dict_calls,
+            self.literal_bytes,
+            self.match_bytes,
+        ]
+    }
+
+    fn counters_mut(&mut self) -> [&mut u64; COUNTERS] {
+        [
+            &mut self.fast_token_hits,
+            &mut self.duplicate_nonoverlap_wild,
+            &mut self.duplicate_near_end_exact_nonoverlap,
+            &mut self.duplicate_overlap_small_u64,
+            &mut self.duplicate_overlap_small_simd,
+            &mut self.duplicate_overlap_large_offset_chunk,
+            &mut self.duplicate_overlap_fallback_byte,
+            &mut self.copy_from_dict_calls,
+            &mut self.literal_bytes,
+            &mut self.match_bytes,
+        ]
+    }
+
+    #[allow(dead_code)]
+    fn from_counters(values: [u64; COUNTERS]) -> Self {
+        let mut snapshot = Self::default();
+        for (a, b) in snapshot.counters_mut().into_iter().zip(values) {
+            *a = b;
+        }
+        snapshot
+    }
+}
+
+const COUNTERS: usize = 10;
+
+/// Indices of the counters, in the field order of [`DecompressProfileSnapshot`].
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+pub(crate) enum Counter {
+    FastTokenHits,
+    DupNonoverlapWild,
+    DupNearEndExactNonoverlap,
+    DupOverlapSmallU64,
+    DupOverlapSmallSimd,
+    DupOverlapLargeOffsetChunk,
+    DupOverlapFallbackByte,
+    CopyFromDictCalls,
+    LiteralBytes,
+    MatchBytes,
+}
+
+#[cfg(feature = "decompress-prof")]
+mod counters {
+    use super::{Counter, DecompressProfileSnapshot, COUNTERS};
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    #[allow(clippy::declare_interior_mutable_const)]
+    const ZERO: AtomicU64 = AtomicU64::new(0);
+    static PROCESS: [AtomicU64; COUNTERS] = [ZERO; COUNTERS];
+
+    // wasm without the `atomics` target feature has a single thread, which sees the process
+    // counters.
+    #[cfg(all(
+        feature = "std",
+        not(all(target_arch = "wasm32", not(target_feature = "atomics")))
+    ))]
+    std::thread_local! {
+        static THREAD: [core::cell::Cell<u64>; COUNTERS] =
+            const { [const { core::cell::Cell::new(0) }; COUNTERS] };
+    }
+
+    #[inline]
+    pub(crate) fn inc(counter: Counter, n: u64) {
+        PROCESS[counter as usize].fetch_add(n, Ordering::Relaxed);
+        #[cfg(all(
+            feature = "std",
+            not(all(target_arch = "wasm32", not(target_feature = "atomics")))
+        ))]
+        let _ = THREAD.try_with(|thread| {
+            let cell = &thread[counter as usize];
+            cell.set(cell.get() + n);
+        });
+    }
+
+    pub(super) fn reset() {
+        for counter in &PROCESS {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub(super) fn snapshot() -> DecompressProfileSnapshot {
+        DecompressProfileSnapshot::from_counters(
+            PROCESS.each_ref().map(|c| c.load(Ordering::Relaxed)),
+        )
+    }
+
+    #[cfg(all(
+        feature = "std",
+        not(all(target_arch = "wasm32", not(target_feature = "atomics")))
+    ))]
+    pub(super) fn reset_thread() {
+        let _ = THREAD.try_with(|thread| thread.iter().for_each(|c| c.set(0)));
+    }
+
+    #[cfg(all(
+        feature = "std",
+        not(all(target_arch = "wasm32", not(target_feature = "atomics")))
+    ))]
+    pub(super) fn snapshot_thread() -> DecompressProfileSnapshot {
+        THREAD
+            .try_with(|thread| {
+                DecompressProfileSnapshot::from_counters(thread.each_ref().map(|c| c.get()))
+            })
+            .unwrap_or_default()
+    }
+
+    #[cfg(all(
+        feature = "std",
+        all(target_arch = "wasm32", not(target_feature = "atomics"))
+    ))]
+    pub(super) use {reset as reset_thread, snapshot as snapshot_thread};
+}
+
+#[cfg(not(feature = "decompress-prof"))]
+mod counters {
+    use super::DecompressProfileSnapshot;
+
+    pub(super) fn reset() {}
+
+    pub(super) fn snapshot() -> DecompressProfileSnapshot {
+        DecompressProfileSnapshot::default()
+    }
+}
+
+#[cfg(feature = "decompress-prof")]
+pub(crate) use counters::inc;
+
+/// Counts `$n` for a [`Counter`] with `decompress-prof`, and compiles to nothing without it.
+#[cfg(feature = "decompress-prof")]
+macro_rules! prof_inc {
+    ($counter:ident, $n:expr) => {
+        crate::block::profile::inc(crate::block::profile::Counter::$counter, $n as u64)
+    };
+}
+
+#[cfg(not(feature = "decompress-prof"))]
+macro_rules! prof_inc {
+    ($counter:ident, $n:expr) => {
+        let _ = &$n;
+    };
+}
+
+pub(crate) use prof_inc;
+
+/// Sets the process-wide counters to zero. The per-thread counters are unchanged.
+#[allow(dead_code)]
+pub fn reset_decompress_profile() {
+    counters::reset();
+}
+
+/// Reads the counters of all threads together since the process started or the last
+/// [`reset_decompress_profile`].
+///
+/// Every decoding thread adds to these counters, so many threads decoding at once contend on
+/// them. The per-thread counters add up to the same totals and can be summed with `+=`.
+#[allow(dead_code)]
+pub fn read_decompress_profile() -> DecompressProfileSnapshot {
+    counters::snapshot()
+}
+
+/// Sets the counters of the calling thread to zero. The process-wide counters are unchanged,
+/// except on wasm without the `atomics` target feature, where they are the same counters.
+#[cfg(all(feature = "decompress-prof", feature = "std"))]
+pub fn reset_thread_decompress_profile() {
+    counters::reset_thread();
+}
+
+/// Reads the counters of blocks decoded by the calling thread since it started or the last
+/// [`reset_thread_decompress_profile`].
+///
+/// On wasm without the `atomics` target feature these are the process-wide counters.
+#[cfg(all(feature = "decompress-prof", feature = "std"))]
+pub fn read_thread_decompress_profile() -> DecompressProfileSnapshot {
+    counters::snapshot_thread()
+}
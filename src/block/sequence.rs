@@ -0,0 +1,132 @@
+//! Bounds-checked parsing of the token, length and offset fields of a block's sequences.
+//!
+//! The safe decoder reads the fields one at a time, so that partial decoding can stop after the
+//! literals. [`next_sequence`] reads a whole sequence, for the functions that walk a block
+//! without decompressing it.
+
+#[cfg(any(feature = "block", test))]
+use crate::block::DecompressError;
+use crate::block::MINMATCH;
+
+/// One sequence of a block: a literal run, followed by a match unless it is the last one.
+#[cfg(any(feature = "block", test))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Sequence {
+    pub literal_length: usize,
+    /// Match offset, 0 for the last sequence.
+    pub offset: usize,
+    /// Match length, 0 for the last sequence.
+    pub match_length: usize,
+}
+
+/// Read an integer.
+///
+/// In LZ4, we encode small integers in a way that we can have an arbitrary number of bytes. In
+/// particular, we add the bytes repeatedly until we hit a non-0xFF byte. When we do, we add
+/// this byte to our sum and terminate the loop.
+///
+/// # Example
+///
+/// ```notest
+///     255, 255, 255, 4, 2, 3, 4, 6, 7
+/// ```
+///
+/// is encoded to _255 + 255 + 255 + 4 = 769_. The bytes after the first 4 is ignored, because
+/// 4 is the first non-0xFF byte.
+///
+/// The sum saturates, a length that large fails the bounds checks that follow.
+#[inline]
+pub(crate) fn read_integer(input: &[u8], input_pos: &mut usize) -> Option<usize> {
+    let mut n: usize = 0;
+    loop {
+        let extra = *input.get(*input_pos)?;
+        *input_pos += 1;
+        n = n.saturating_add(extra as usize);
+        if extra != 0xFF {
+            return Some(n);
+        }
+    }
+}
+
+/// Read a little-endian 16-bit integer from the input stream.
+#[inline]
+pub(crate) fn read_u16(input: &[u8], input_pos: &mut usize) -> Option<u16> {
+    let dst = input.get(*input_pos..*input_pos + 2)?;
+    *input_pos += 2;
+    Some(u16::from_le_bytes(dst.try_into().unwrap()))
+}
+
+/// Reads the literal length of `token`, followed by its continuation bytes at `*input_pos` if
+/// the upper nibble is 15.
+#[inline]
+pub(crate) fn read_literal_length(token: u8, input: &[u8], input_pos: &mut usize) -> Option<usize> {
+    let literal_length = (token >> 4) as usize;
+    if literal_length == 15 {
+        return Some(literal_length.saturating_add(read_integer(input, input_pos)?));
+    }
+    Some(literal_length)
+}
+
+/// Reads the match length of `token`, followed by its continuation bytes at `*input_pos` if
+/// the lower nibble is 15.
+#[inline]
+pub(crate) fn read_match_length(token: u8, input: &[u8], input_pos: &mut usize) -> Option<usize> {
+    let match_length = MINMATCH + (token & 0xF) as usize;
+    if match_length == MINMATCH + 15 {
+        return Some(match_length.saturating_add(read_integer(input, input_pos)?));
+    }
+    Some(match_length)
+}
+
+/// Parses the sequence starting at `*input_pos` and advances past it, with the same bounds
+/// checks as the decoders. Returns `None` once the input is exhausted.
+///
+/// `output_pos` is the decompressed length so far; it is only used to report errors.
+#[cfg(any(feature = "block", test))]
+#[inline]
+pub(crate) fn next_sequence(
+    input: &[u8],
+    input_pos: &mut usize,
+    output_pos: usize,
+) -> Result<Option<Sequence>, DecompressError> {
+    if *input_pos >= input.len() {
+        return Ok(None);
+    }
+    let token_pos = *input_pos;
+    let token = input[*input_pos];
+    *input_pos += 1;
+    let expected_another_byte = || DecompressError::ExpectedAnotherByte {
+        input_pos: token_pos,
+        output_pos,
+    };
+
+    let literal_length =
+        read_literal_length(token, input, input_pos).ok_or_else(expected_another_byte)?;
+    if literal_length > input.len() - *input_pos {
+        return Err(DecompressError::LiteralOutOfBounds {
+            input_pos: token_pos,
+            output_pos,
+            literal_length,
+        });
+    }
+    *input_pos += literal_length;
+
+    let mut sequence = Sequence {
+        literal_length,
+        offset: 0,
+        match_length: 0,
+    };
+    // The last sequence has only literals.
+    if *input_pos >= input.len() {
+        return Ok(Some(sequence));
+    }
+
+    sequence.offset = read_u16(input, input_pos).ok_or_else(expected_another_byte)? as usize;
+    sequence.match_length =
+        read_match_length(token, input, input_pos).ok_or_else(expected_another_byte)?;
+    // A match is always followed by another sequence.
+    if *input_pos >= input.len() {
+        return Err(expected_another_byte());
+    }
+    Ok(Some(sequence))
+}
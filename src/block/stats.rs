@@ -0,0 +1,164 @@
+//! Statistics collected by the block compressor.
+
+use core::ops::AddAssign;
+
+/// Number of buckets of the [`CompressStats`] histograms.
+pub const HISTOGRAM_BUCKETS: usize = 17;
+
+/// What the compressor did on one or more blocks, to tune block sizes and dictionaries for a
+/// data set.
+///
+/// Collected by the `*_with_stats` functions of the [`block`](crate::block) module, e.g.
+/// [`compress_with_stats`](crate::block::compress_with_stats), and by `FrameEncoder` after
+/// `enable_stats`. Counters add up over calls, so one value can cover many blocks, and values
+/// can be summed with `+=`.
+///
+/// The histograms have power of two buckets: bucket `i` counts values in `2^i..2^(i + 1)`, the
+/// last bucket also the values above. Match lengths start at 4, so their first two buckets stay
+/// empty.
+///
+/// # Example
+#[cfg_attr(feature = "block", doc = "```")]
+#[cfg_attr(not(feature = "block"), doc = "```ignore")]
+/// use lz4_flex_wasm_simd::block::{compress_with_stats, decompress};
+/// let input: Vec<u8> = br#"{"id":1,"tags":["a","b"]},"#.repeat(100);
+/// let (compressed, stats) = compress_with_stats(&input);
+/// assert_eq!(decompress(&compressed, input.len()).unwrap(), input);
+/// assert_eq!(stats.input_bytes, input.len() as u64);
+/// assert_eq!(stats.output_bytes, compressed.len() as u64);
+/// // Most of the input repeats the first record, 26 bytes back.
+/// assert!(stats.offsets[4] > 0);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CompressStats {
+    /// Blocks compressed.
+    pub blocks: u64,
+    /// Uncompressed bytes of the blocks.
+    pub input_bytes: u64,
+    /// Compressed bytes of the blocks.
+    pub output_bytes: u64,
+    /// Input bytes written as literals, including the last literals of each block.
+    pub literal_bytes: u64,
+    /// Matches written.
+    pub matches: u64,
+    /// Input bytes covered by matches.
+    pub match_bytes: u64,
+    /// Histogram of the match lengths.
+    pub match_lengths: [u64; HISTOGRAM_BUCKETS],
+    /// Histogram of the match offsets, the distance back to the repeated bytes.
+    pub offsets: [u64; HISTOGRAM_BUCKETS],
+    /// Hash table lookups that found a match of at least four bytes.
+    pub hash_hits: u64,
+    /// Lookups whose candidate was out of reach: more than 64KB back, or not part of the input,
+    /// prefix or dictionary.
+    pub hash_misses: u64,
+    /// Lookups whose candidate was in reach but did not match: a hash collision, or a slot that
+    /// was never filled.
+    pub hash_collisions: u64,
+    /// Times the search skipped ahead faster after 32 more failed lookups in a row, which
+    /// happens on data that does not compress.
+    pub step_escalations: u64,
+}
+
+impl CompressStats {
+    /// Creates empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AddAssign<&CompressStats> for CompressStats {
+    fn add_assign(&mut self, other: &CompressStats) {
+        self.blocks += other.blocks;
+        self.input_bytes += other.input_bytes;
+        self.output_bytes += other.output_bytes;
+        self.literal_bytes += other.literal_bytes;
+        self.matches += other.matches;
+        self.match_bytes += other.match_bytes;
+        for (a, b) in self.match_lengths.iter_mut().zip(&other.match_lengths) {
+            *a += b;
+        }
+        for (a, b) in self.offsets.iter_mut().zip(&other.offsets) {
+            *a += b;
+        }
+        self.hash_hits += other.hash_hits;
+        self.hash_misses += other.hash_misses;
+        self.hash_collisions += other.hash_collisions;
+        self.step_escalations += other.step_escalations;
+    }
+}
+
+/// Histogram bucket of `value`, which must not be 0.
+#[inline]
+fn bucket(value: usize) -> usize {
+    (value.ilog2() as usize).min(HISTOGRAM_BUCKETS - 1)
+}
+
+/// Receives the events of the compression loop. `()` ignores them, so the compressor compiles
+/// to the same code as without statistics.
+pub(crate) trait StatsRecorder {
+    #[inline(always)]
+    fn hash_hit(&mut self) {}
+    #[inline(always)]
+    fn hash_miss(&mut self) {}
+    #[inline(always)]
+    fn hash_collision(&mut self) {}
+    #[inline(always)]
+    fn step_escalation(&mut self) {}
+    /// A sequence with `literal_len` literals and a match of `match_len` bytes (at least
+    /// `MINMATCH`) at `offset`.
+    #[inline(always)]
+    fn sequence(&mut self, _literal_len: usize, _match_len: usize, _offset: u16) {}
+    /// The literals that end a block.
+    #[inline(always)]
+    fn last_literals(&mut self, _len: usize) {}
+    /// A finished block.
+    #[inline(always)]
+    fn block(&mut self, _input_len: usize, _output_len: usize) {}
+}
+
+impl StatsRecorder for () {}
+
+impl StatsRecorder for CompressStats {
+    #[inline]
+    fn hash_hit(&mut self) {
+        self.hash_hits += 1;
+    }
+
+    #[inline]
+    fn hash_miss(&mut self) {
+        self.hash_misses += 1;
+    }
+
+    #[inline]
+    fn hash_collision(&mut self) {
+        self.hash_collisions += 1;
+    }
+
+    #[inline]
+    fn step_escalation(&mut self) {
+        self.step_escalations += 1;
+    }
+
+    #[inline]
+    fn sequence(&mut self, literal_len: usize, match_len: usize, offset: u16) {
+        self.literal_bytes += literal_len as u64;
+        self.matches += 1;
+        self.match_bytes += match_len as u64;
+        self.match_lengths[bucket(match_len)] += 1;
+        self.offsets[bucket(offset as usize)] += 1;
+    }
+
+    #[inline]
+    fn last_literals(&mut self, len: usize) {
+        self.literal_bytes += len as u64;
+    }
+
+    #[inline]
+    fn block(&mut self, input_len: usize, output_len: usize) {
+        self.blocks += 1;
+        self.input_bytes += input_len as u64;
+        self.output_bytes += output_len as u64;
+    }
+}
@@ -8,8 +8,10 @@ use std::{
 
 use crate::{
     block::{
-        compress::compress_internal,
-        hashtable::{HashTable, HashTable4K},
+        compress::{compress_internal, DEFAULT_MEMORY_USAGE},
+        hashtable::{HashTable, HashTableU32},
+        stats::{CompressStats, StatsRecorder},
+        CompressError,
     },
     sink::vec_sink_for_compression,
 };
@@ -60,7 +62,13 @@ use crate::block::WINDOW_SIZE;
 /// }
 /// compressor.finish().unwrap();
 /// ```
-pub struct FrameEncoder<W: io::Write> {
+///
+/// # Hash table size
+/// `MEMORY_USAGE` sets the size of the match table to `2^MEMORY_USAGE` bytes, see
+/// [`block::Compressor`](crate::block::Compressor). Encoders with another size than
+/// [`DEFAULT_MEMORY_USAGE`](crate::block::DEFAULT_MEMORY_USAGE) are created with
+/// [`with_memory_usage()`](Self::with_memory_usage).
+pub struct FrameEncoder<W: io::Write, const MEMORY_USAGE: u32 = DEFAULT_MEMORY_USAGE> {
     /// Our buffer of uncompressed bytes.
     src: Vec<u8>,
     /// Index into src: starting point of bytes not yet compressed
@@ -75,7 +83,7 @@ pub struct FrameEncoder<W: io::Write> {
     /// _Not_ the same as `content_len` as this is reset every to 2GB.
     src_stream_offset: usize,
     /// Encoder table
-    compression_table: HashTable4K,
+    compression_table: HashTableU32<MEMORY_USAGE>,
     /// The underlying writer.
     w: W,
     /// Xxhash32 used when content checksum is enabled.
@@ -91,9 +99,112 @@ pub struct FrameEncoder<W: io::Write> {
     data_to_frame_written: bool,
     /// The frame information to be used in this encoder.
     frame_info: FrameInfo,
+    /// Statistics of the compressed blocks, if enabled.
+    stats: Option<CompressStats>,
+    /// When to call [`sync_flush()`](Self::sync_flush) without being asked.
+    auto_flush: AutoFlush,
+    /// Uncompressed bytes written since the last sync flush.
+    unsynced_len: usize,
+    /// Ticks seen while `unsynced_len` was not 0.
+    unsynced_ticks: u32,
+}
+
+/// When a [`FrameEncoder`] flushes on its own, set with
+/// [`FrameEncoder::set_auto_flush`].
+///
+/// Each auto flush is a [`FrameEncoder::sync_flush`]: it ends the current block and flushes the
+/// inner writer, so a reader of the output so far can decode every byte written before it. Short
+/// blocks compress worse, so the limits trade ratio for latency. The default never flushes on
+/// its own.
+///
+/// ```
+/// use std::io::Write;
+/// use lz4_flex_wasm_simd::frame::{AutoFlush, FrameEncoder};
+/// let mut encoder = FrameEncoder::new(Vec::new());
+/// // Flush after 4KB, or on the second tick with data pending, whichever comes first.
+/// encoder.set_auto_flush(AutoFlush::new().bytes(4096).ticks(2));
+/// encoder.write_all(b"GET /index.html 200\n").unwrap();
+/// encoder.tick().unwrap(); // e.g. from a 50ms timer
+/// let pending = encoder.get_ref().len();
+/// encoder.tick().unwrap();
+/// assert!(encoder.get_ref().len() > pending);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AutoFlush {
+    /// Flush once this many uncompressed bytes were written since the last flush. Writes are
+    /// split at this point, so no more than this many bytes wait in the encoder.
+    pub bytes: Option<usize>,
+    /// Flush on this many calls to [`FrameEncoder::tick`] while data is pending. With 1 every
+    /// tick flushes the pending data; with 2 data waits at least one full tick interval.
+    pub ticks: Option<u32>,
+}
+
+impl AutoFlush {
+    /// Creates a policy that never flushes on its own.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flush once `bytes` uncompressed bytes were written since the last flush. 0 counts as 1.
+    pub fn bytes(mut self, bytes: usize) -> Self {
+        self.bytes = Some(bytes);
+        self
+    }
+
+    /// Flush on the `ticks`-th call to [`FrameEncoder::tick`] while data is pending. 0 counts
+    /// as 1.
+    pub fn ticks(mut self, ticks: u32) -> Self {
+        self.ticks = Some(ticks);
+        self
+    }
 }
 
 impl<W: io::Write> FrameEncoder<W> {
+    /// Creates a new Encoder with the specified FrameInfo.
+    pub fn with_frame_info(frame_info: FrameInfo, wtr: W) -> Self {
+        Self::with_memory_usage(frame_info, wtr)
+    }
+
+    /// Creates a new Encoder with the default settings.
+    pub fn new(wtr: W) -> Self {
+        Self::with_frame_info(Default::default(), wtr)
+    }
+}
+
+impl<W: io::Write, const MEMORY_USAGE: u32> FrameEncoder<W, MEMORY_USAGE> {
+    /// Creates a new Encoder with the specified FrameInfo and a match table of
+    /// `2^MEMORY_USAGE` bytes.
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use lz4_flex_wasm_simd::frame::{FrameEncoder, FrameInfo};
+    /// let mut encoder = FrameEncoder::<_, 16>::with_memory_usage(FrameInfo::new(), Vec::new());
+    /// encoder.write_all(b"a larger table finds more matches").unwrap();
+    /// let compressed = encoder.finish().unwrap();
+    /// ```
+    pub fn with_memory_usage(frame_info: FrameInfo, wtr: W) -> Self {
+        FrameEncoder {
+            src: Vec::new(),
+            w: wtr,
+            compression_table: HashTableU32::new(),
+            content_hasher: XxHash32::with_seed(0),
+            content_len: 0,
+            dst: Vec::new(),
+            is_frame_open: false,
+            data_to_frame_written: false,
+            frame_info,
+            src_start: 0,
+            src_end: 0,
+            ext_dict_offset: 0,
+            ext_dict_len: 0,
+            src_stream_offset: 0,
+            stats: None,
+            auto_flush: AutoFlush::default(),
+            unsynced_len: 0,
+            unsynced_ticks: 0,
+        }
+    }
+
     fn init(&mut self) {
         let max_block_size = self.frame_info.block_size.get_size();
         let src_size = if self.frame_info.block_mode == BlockMode::Linked {
@@ -126,41 +237,86 @@ impl<W: io::Write> FrameEncoder<W> {
     ///
     /// [`finish()`]: Self::finish
     /// [`try_finish()`]: Self::try_finish
-    pub fn auto_finish(self) -> AutoFinishEncoder<W> {
+    pub fn auto_finish(self) -> AutoFinishEncoder<W, MEMORY_USAGE> {
         AutoFinishEncoder {
             encoder: Some(self),
         }
     }
 
-    /// Creates a new Encoder with the specified FrameInfo.
-    pub fn with_frame_info(frame_info: FrameInfo, wtr: W) -> Self {
-        FrameEncoder {
-            src: Vec::new(),
-            w: wtr,
-            // 16 KB hash table for matches, same as the reference implementation.
-            compression_table: HashTable4K::new(),
-            content_hasher: XxHash32::with_seed(0),
-            content_len: 0,
-            dst: Vec::new(),
-            is_frame_open: false,
-            data_to_frame_written: false,
-            frame_info,
-            src_start: 0,
-            src_end: 0,
-            ext_dict_offset: 0,
-            ext_dict_len: 0,
-            src_stream_offset: 0,
+    /// The frame information used by this Encoder.
+    pub fn frame_info(&mut self) -> &FrameInfo {
+        &self.frame_info
+    }
+
+    /// Starts collecting [`CompressStats`] for the blocks compressed from now on, read with
+    /// [`stats()`](Self::stats). Blocks that do not shrink are stored uncompressed, their
+    /// `output_bytes` still count the compressed size.
+    ///
+    /// Collecting statistics makes compression slower.
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use lz4_flex_wasm_simd::frame::FrameEncoder;
+    /// let mut encoder = FrameEncoder::new(Vec::new());
+    /// encoder.enable_stats();
+    /// encoder.write_all(&b"a log line that repeats\n".repeat(100)).unwrap();
+    /// encoder.flush().unwrap();
+    /// let stats = encoder.stats().unwrap();
+    /// assert_eq!(stats.blocks, 1);
+    /// assert!(stats.matches > 0);
+    /// ```
+    pub fn enable_stats(&mut self) {
+        self.stats.get_or_insert_with(CompressStats::new);
+    }
+
+    /// The statistics collected since [`enable_stats()`](Self::enable_stats), if enabled.
+    pub fn stats(&self) -> Option<&CompressStats> {
+        self.stats.as_ref()
+    }
+
+    /// Sets when the encoder flushes on its own, see [`AutoFlush`].
+    pub fn set_auto_flush(&mut self, auto_flush: AutoFlush) {
+        self.auto_flush = auto_flush;
+    }
+
+    /// Compresses the buffered bytes into a block and writes it to the inner writer, without
+    /// flushing the writer.
+    ///
+    /// Use it to bound how much uncompressed data the encoder holds; the block may still wait
+    /// in a buffering writer such as `BufWriter`.
+    pub fn flush_block(&mut self) -> io::Result<()> {
+        if self.src_start != self.src_end {
+            self.write_block()?;
         }
+        Ok(())
     }
 
-    /// Creates a new Encoder with the default settings.
-    pub fn new(wtr: W) -> Self {
-        Self::with_frame_info(Default::default(), wtr)
+    /// Writes the buffered bytes as a block like [`flush_block()`](Self::flush_block), then
+    /// flushes the inner writer. This is what [`Write::flush`] does.
+    ///
+    /// Afterwards a reader of the output can decode every byte written so far, without waiting
+    /// for the end of the frame.
+    pub fn sync_flush(&mut self) -> io::Result<()> {
+        self.flush_block()?;
+        self.w.flush()?;
+        self.unsynced_len = 0;
+        self.unsynced_ticks = 0;
+        Ok(())
     }
 
-    /// The frame information used by this Encoder.
-    pub fn frame_info(&mut self) -> &FrameInfo {
-        &self.frame_info
+    /// Advances the clock of the [`AutoFlush::ticks`] policy, e.g. from a timer of the caller.
+    /// Flushes with [`sync_flush()`](Self::sync_flush) once data has been pending for that many
+    /// ticks, and does nothing without pending data or a tick policy.
+    pub fn tick(&mut self) -> io::Result<()> {
+        if let Some(ticks) = self.auto_flush.ticks {
+            if self.unsynced_len != 0 {
+                self.unsynced_ticks += 1;
+                if self.unsynced_ticks >= ticks {
+                    self.sync_flush()?;
+                }
+            }
+        }
+        Ok(())
     }
 
     /// Consumes this encoder, flushing internal buffer and writing stream terminator.
@@ -172,7 +328,7 @@ impl<W: io::Write> FrameEncoder<W> {
     /// Attempt to finish this output stream, flushing internal buffer and writing stream
     /// terminator.
     pub fn try_finish(&mut self) -> Result<(), Error> {
-        match self.flush() {
+        match self.flush_block() {
             Ok(()) => {
                 // Empty input special case
                 // https://github.com/ouch-org/ouch/pull/163#discussion_r1108965151
@@ -257,48 +413,67 @@ impl<W: io::Write> FrameEncoder<W> {
         Ok(())
     }
 
-    /// Consumes the src contents between src_start and src_end,
-    /// which shouldn't exceed the max block size.
-    fn write_block(&mut self) -> io::Result<()> {
-        debug_assert!(self.is_frame_open);
-        let max_block_size = self.frame_info.block_size.get_size();
-        debug_assert!(self.src_end - self.src_start <= max_block_size);
-
-        // Reposition the compression table if we're anywhere near an overflowing hazard
-        if self.src_stream_offset + max_block_size + WINDOW_SIZE >= u32::MAX as usize / 2 {
-            self.compression_table
-                .reposition((self.src_stream_offset - self.ext_dict_len) as _);
-            self.src_stream_offset = self.ext_dict_len;
-        }
-
+    /// Compresses the src contents between src_start and src_end into dst.
+    fn compress_block(
+        &mut self,
+        dst_required_size: usize,
+        stats: &mut impl StatsRecorder,
+    ) -> Result<usize, CompressError> {
         // input to the compressor, which may include a prefix when blocks are linked
         let input = &self.src[..self.src_end];
-        // the contents of the block are between src_start and src_end
-        let src = &input[self.src_start..];
-
-        let dst_required_size = crate::block::compress::get_maximum_output_size(src.len());
-
-        let compress_result = if self.ext_dict_len != 0 {
+        if self.ext_dict_len != 0 {
             debug_assert_eq!(self.frame_info.block_mode, BlockMode::Linked);
-            compress_internal::<_, true, _>(
+            compress_internal::<_, true, _, _>(
                 input,
                 self.src_start,
                 &mut vec_sink_for_compression(&mut self.dst, 0, 0, dst_required_size),
                 &mut self.compression_table,
                 &self.src[self.ext_dict_offset..self.ext_dict_offset + self.ext_dict_len],
                 self.src_stream_offset,
+                stats,
             )
         } else {
-            compress_internal::<_, false, _>(
+            compress_internal::<_, false, _, _>(
                 input,
                 self.src_start,
                 &mut vec_sink_for_compression(&mut self.dst, 0, 0, dst_required_size),
                 &mut self.compression_table,
                 b"",
                 self.src_stream_offset,
+                stats,
             )
+        }
+    }
+
+    /// Consumes the src contents between src_start and src_end,
+    /// which shouldn't exceed the max block size.
+    fn write_block(&mut self) -> io::Result<()> {
+        debug_assert!(self.is_frame_open);
+        let max_block_size = self.frame_info.block_size.get_size();
+        debug_assert!(self.src_end - self.src_start <= max_block_size);
+
+        // Reposition the compression table if we're anywhere near an overflowing hazard
+        if self.src_stream_offset + max_block_size + WINDOW_SIZE >= u32::MAX as usize / 2 {
+            self.compression_table
+                .reposition((self.src_stream_offset - self.ext_dict_len) as _);
+            self.src_stream_offset = self.ext_dict_len;
+        }
+
+        let dst_required_size =
+            crate::block::compress::get_maximum_output_size(self.src_end - self.src_start);
+
+        let compress_result = match self.stats.take() {
+            Some(mut stats) => {
+                let result = self.compress_block(dst_required_size, &mut stats);
+                self.stats = Some(stats);
+                result
+            }
+            None => self.compress_block(dst_required_size, &mut ()),
         };
 
+        // the contents of the block are between src_start and src_end
+        let src = &self.src[self.src_start..self.src_end];
+
         let (block_info, block_data) = match compress_result.map_err(Error::CompressionError)? {
             comp_len if comp_len < src.len() => {
                 (BlockInfo::Compressed(comp_len as _), &self.dst[..comp_len])
@@ -370,14 +545,9 @@ impl<W: io::Write> FrameEncoder<W> {
         debug_assert!(self.src_start + max_block_size <= self.src.capacity());
         Ok(())
     }
-}
 
-impl<W: io::Write> io::Write for FrameEncoder<W> {
-    fn write(&mut self, mut buf: &[u8]) -> io::Result<usize> {
-        if !self.is_frame_open && !buf.is_empty() {
-            self.begin_frame(buf.len())?;
-        }
-        let buf_len = buf.len();
+    /// Buffers `buf`, writing blocks as they fill up.
+    fn write_to_blocks(&mut self, mut buf: &[u8]) -> io::Result<()> {
         while !buf.is_empty() {
             let src_filled = self.src_end - self.src_start;
             let max_fill_len = self.frame_info.block_size.get_size() - src_filled;
@@ -393,14 +563,39 @@ impl<W: io::Write> io::Write for FrameEncoder<W> {
             buf = &buf[fill_len..];
             self.src_end += fill_len;
         }
+        Ok(())
+    }
+}
+
+impl<W: io::Write, const MEMORY_USAGE: u32> io::Write for FrameEncoder<W, MEMORY_USAGE> {
+    fn write(&mut self, mut buf: &[u8]) -> io::Result<usize> {
+        if !self.is_frame_open && !buf.is_empty() {
+            self.begin_frame(buf.len())?;
+        }
+        let buf_len = buf.len();
+        let Some(limit) = self.auto_flush.bytes else {
+            self.write_to_blocks(buf)?;
+            self.unsynced_len = self.unsynced_len.saturating_add(buf_len);
+            return Ok(buf_len);
+        };
+        let limit = limit.max(1);
+        while !buf.is_empty() {
+            let len = buf
+                .len()
+                .min(limit.saturating_sub(self.unsynced_len).max(1));
+            self.write_to_blocks(&buf[..len])?;
+            buf = &buf[len..];
+            self.unsynced_len += len;
+            if self.unsynced_len >= limit {
+                self.sync_flush()?;
+            }
+        }
         Ok(buf_len)
     }
 
+    /// Flushes the buffered bytes and the inner writer, see [`FrameEncoder::sync_flush`].
     fn flush(&mut self) -> io::Result<()> {
-        if self.src_start != self.src_end {
-            self.write_block()?;
-        }
-        Ok(())
+        self.sync_flush()
     }
 }
 
@@ -415,12 +610,12 @@ impl<W: io::Write> io::Write for FrameEncoder<W> {
 /// [`finish()`]: FrameEncoder::finish
 /// [`try_finish()`]: FrameEncoder::try_finish
 /// [`auto_finish()`]: FrameEncoder::auto_finish
-pub struct AutoFinishEncoder<W: Write> {
+pub struct AutoFinishEncoder<W: Write, const MEMORY_USAGE: u32 = DEFAULT_MEMORY_USAGE> {
     // We wrap this in an option to take it during drop.
-    encoder: Option<FrameEncoder<W>>,
+    encoder: Option<FrameEncoder<W, MEMORY_USAGE>>,
 }
 
-impl<W: io::Write> Drop for AutoFinishEncoder<W> {
+impl<W: io::Write, const MEMORY_USAGE: u32> Drop for AutoFinishEncoder<W, MEMORY_USAGE> {
     fn drop(&mut self) {
         if let Some(mut encoder) = self.encoder.take() {
             let _ = encoder.try_finish();
@@ -428,7 +623,7 @@ impl<W: io::Write> Drop for AutoFinishEncoder<W> {
     }
 }
 
-impl<W: Write> Write for AutoFinishEncoder<W> {
+impl<W: Write, const MEMORY_USAGE: u32> Write for AutoFinishEncoder<W, MEMORY_USAGE> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         self.encoder.as_mut().unwrap().write(buf)
     }
@@ -438,7 +633,9 @@ impl<W: Write> Write for AutoFinishEncoder<W> {
     }
 }
 
-impl<W: fmt::Debug + io::Write> fmt::Debug for FrameEncoder<W> {
+impl<W: fmt::Debug + io::Write, const MEMORY_USAGE: u32> fmt::Debug
+    for FrameEncoder<W, MEMORY_USAGE>
+{
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("FrameEncoder")
             .field("w", &self.w)
@@ -455,6 +652,10 @@ impl<W: fmt::Debug + io::Write> fmt::Debug for FrameEncoder<W> {
             .field("ext_dict_offset", &self.ext_dict_offset)
             .field("ext_dict_len", &self.ext_dict_len)
             .field("src_stream_offset", &self.src_stream_offset)
+            .field("stats", &self.stats)
+            .field("auto_flush", &self.auto_flush)
+            .field("unsynced_len", &self.unsynced_len)
+            .field("unsynced_ticks", &self.unsynced_ticks)
             .finish()
     }
 }
@@ -12,10 +12,7 @@ use super::header::{
     MAX_FRAME_INFO_SIZE, MIN_FRAME_INFO_SIZE,
 };
 use super::Error;
-use crate::{
-    block::WINDOW_SIZE,
-    sink::{vec_sink_for_decompression, SliceSink},
-};
+use crate::{block::WINDOW_SIZE, sink::SliceSink};
 
 /// A reader for decompressing the LZ4 frame format
 ///
@@ -57,6 +54,10 @@ pub struct FrameDecoder<R: io::Read> {
     content_hasher: XxHash32,
     /// Total length of decompressed output for the current frame.
     content_len: u64,
+    /// Index of the next block of the current frame.
+    block_index: u64,
+    /// Number of bytes of the current frame read so far, including the header.
+    frame_pos: u64,
     /// The compressed bytes buffer, taken from the underlying reader.
     src: Vec<u8>,
     /// The decompressed bytes buffer. Bytes are decompressed from src to dst
@@ -86,6 +87,8 @@ impl<R: io::Read> FrameDecoder<R> {
             current_frame_info: None,
             content_hasher: XxHash32::with_seed(0),
             content_len: 0,
+            block_index: 0,
+            frame_pos: 0,
         }
     }
 
@@ -162,6 +165,8 @@ impl<R: io::Read> FrameDecoder<R> {
         self.current_frame_info = Some(frame_info);
         self.content_hasher = XxHash32::with_seed(0);
         self.content_len = 0;
+        self.block_index = 0;
+        self.frame_pos = required as u64;
         self.ext_dict_len = 0;
         self.dst_start = 0;
         self.dst_end = 0;
@@ -240,6 +245,12 @@ impl<R: io::Read> FrameDecoder<R> {
             }
             BlockInfo::read(&buffer)?
         };
+        let block_index = self.block_index;
+        let block_offset = self.frame_pos;
+        if let BlockInfo::Uncompressed(len) | BlockInfo::Compressed(len) = block_info {
+            self.block_index += 1;
+            self.frame_pos += 4 + len as u64 + if frame_info.block_checksums { 4 } else { 0 };
+        }
         match block_info {
             BlockInfo::Uncompressed(len) => {
                 let len = len as usize;
@@ -286,7 +297,7 @@ impl<R: io::Read> FrameDecoder<R> {
                     let ext_dict = &tail[..self.ext_dict_len];
 
                     debug_assert!(head.len() - self.dst_start >= max_block_size);
-                    crate::block::decompress::decompress_internal::<true, _>(
+                    crate::block::decompress::decompress_internal::<true, false, _>(
                         &self.src[..len],
                         &mut SliceSink::new(head, self.dst_start),
                         ext_dict,
@@ -294,18 +305,18 @@ impl<R: io::Read> FrameDecoder<R> {
                 } else {
                     // Independent blocks OR linked blocks with only prefix data
                     debug_assert!(self.dst.capacity() - self.dst_start >= max_block_size);
-                    crate::block::decompress::decompress_internal::<false, _>(
+                    crate::block::decompress::decompress_into_vec(
                         &self.src[..len],
-                        &mut vec_sink_for_decompression(
-                            &mut self.dst,
-                            0,
-                            self.dst_start,
-                            self.dst_start + max_block_size,
-                        ),
-                        b"",
+                        &mut self.dst,
+                        self.dst_start,
+                        max_block_size,
                     )
                 }
-                .map_err(Error::DecompressionError)?;
+                .map_err(|error| Error::DecompressionError {
+                    error,
+                    block_index,
+                    frame_offset: block_offset,
+                })?;
 
                 self.dst_end += decomp_size;
                 self.content_len += decomp_size as u64;
@@ -428,6 +439,8 @@ impl<R: fmt::Debug + io::Read> fmt::Debug for FrameDecoder<R> {
             .field("r", &self.r)
             .field("content_hasher", &self.content_hasher)
             .field("content_len", &self.content_len)
+            .field("block_index", &self.block_index)
+            .field("frame_pos", &self.frame_pos)
             .field("src", &"[...]")
             .field("dst", &"[...]")
             .field("dst_start", &self.dst_start)
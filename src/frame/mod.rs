@@ -26,7 +26,18 @@ pub(crate) mod compress;
 pub(crate) mod decompress;
 pub(crate) mod header;
 
-pub use compress::{AutoFinishEncoder, FrameEncoder};
+#[cfg(feature = "compress-prof")]
+pub use crate::block::compress::{
+    read_compress_profile, reset_compress_profile, CompressProfileSnapshot,
+};
+#[cfg(feature = "decompress-prof")]
+pub use crate::block::profile::{
+    read_decompress_profile, read_thread_decompress_profile, reset_decompress_profile,
+    reset_thread_decompress_profile, DecompressProfileSnapshot,
+};
+pub use crate::block::stats::{CompressStats, HISTOGRAM_BUCKETS};
+pub use crate::block::{CompressError, DecompressError};
+pub use compress::{AutoFinishEncoder, AutoFlush, FrameEncoder};
 pub use decompress::FrameDecoder;
 pub use header::{BlockMode, BlockSize, FrameInfo};
 
@@ -35,9 +46,16 @@ pub use header::{BlockMode, BlockSize, FrameInfo};
 /// Errors that can occur when de/compressing lz4.
 pub enum Error {
     /// Compression error.
-    CompressionError(crate::block::CompressError),
+    CompressionError(CompressError),
     /// Decompression error.
-    DecompressionError(crate::block::DecompressError),
+    DecompressionError {
+        /// The error reported by the block decoder.
+        error: DecompressError,
+        /// Index of the failing block within its frame, starting at 0.
+        block_index: u64,
+        /// Offset of the failing block's header from the start of its frame.
+        frame_offset: u64,
+    },
     /// An io::Error was encountered.
     IoError(io::Error),
     /// Unsupported block size.
@@ -77,7 +95,7 @@ impl From<Error> for io::Error {
         match e {
             Error::IoError(e) => e,
             Error::CompressionError(_)
-            | Error::DecompressionError(_)
+            | Error::DecompressionError { .. }
             | Error::SkippableFrame(_)
             | Error::DictionaryNotSupported => io::Error::other(e),
             Error::WrongMagicNumber
@@ -109,4 +127,11 @@ impl fmt::Display for Error {
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::DecompressionError { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
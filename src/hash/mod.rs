@@ -1,5 +1,10 @@
-//! Internal hash primitives required by the frame codec.
+//! Hash primitives: xxHash32 is required by the frame codec, `XxHash64` and XXH3 are
+//! provided for hashing payloads without pulling in a second hash crate.
 
+pub mod xxh3;
 pub mod xxhash32;
+pub mod xxhash64;
 
+pub use xxh3::{XxHash3_128, XxHash3_64};
 pub use xxhash32::XxHash32;
+pub use xxhash64::XxHash64;
@@ -0,0 +1,734 @@
+//! Minimal XXH3 (64 and 128 bit) implementation for hashing payloads.
+//!
+//! Only the default secret and seeded variants are supported. Inputs longer than 240 bytes
+//! go through the stripe accumulator, which uses wasm SIMD128 when it is enabled.
+
+use super::xxhash64::{avalanche as xxh64_avalanche, PRIME64_1, PRIME64_2, PRIME64_3};
+use super::xxhash64::{PRIME64_4, PRIME64_5};
+use core::hash::Hasher;
+
+const PRIME32_1: u64 = 0x9E37_79B1;
+const PRIME32_2: u64 = 0x85EB_CA77;
+const PRIME32_3: u64 = 0xC2B2_AE3D;
+const PRIME_MX1: u64 = 0x1656_6791_9E37_79F9;
+const PRIME_MX2: u64 = 0x9FB2_1C65_1E98_DF25;
+
+const STRIPE_LEN: usize = 64;
+const SECRET_CONSUME_RATE: usize = 8;
+const ACC_NB: usize = STRIPE_LEN / 8;
+const SECRET_SIZE: usize = 192;
+const SECRET_LASTACC_START: usize = 7;
+const SECRET_MERGEACCS_START: usize = 11;
+const MIDSIZE_MAX: usize = 240;
+const MIDSIZE_STARTOFFSET: usize = 3;
+const MIDSIZE_LASTOFFSET: usize = 17;
+const STRIPES_PER_BLOCK: usize = (SECRET_SIZE - STRIPE_LEN) / SECRET_CONSUME_RATE;
+const BUFFER_SIZE: usize = 256;
+const BUFFER_STRIPES: usize = BUFFER_SIZE / STRIPE_LEN;
+
+const DEFAULT_SECRET: [u8; SECRET_SIZE] = [
+    0xb8, 0xfe, 0x6c, 0x39, 0x23, 0xa4, 0x4b, 0xbe, 0x7c, 0x01, 0x81, 0x2c, 0xf7, 0x21, 0xad, 0x1c,
+    0xde, 0xd4, 0x6d, 0xe9, 0x83, 0x90, 0x97, 0xdb, 0x72, 0x40, 0xa4, 0xa4, 0xb7, 0xb3, 0x67, 0x1f,
+    0xcb, 0x79, 0xe6, 0x4e, 0xcc, 0xc0, 0xe5, 0x78, 0x82, 0x5a, 0xd0, 0x7d, 0xcc, 0xff, 0x72, 0x21,
+    0xb8, 0x08, 0x46, 0x74, 0xf7, 0x43, 0x24, 0x8e, 0xe0, 0x35, 0x90, 0xe6, 0x81, 0x3a, 0x26, 0x4c,
+    0x3c, 0x28, 0x52, 0xbb, 0x91, 0xc3, 0x00, 0xcb, 0x88, 0xd0, 0x65, 0x8b, 0x1b, 0x53, 0x2e, 0xa3,
+    0x71, 0x64, 0x48, 0x97, 0xa2, 0x0d, 0xf9, 0x4e, 0x38, 0x19, 0xef, 0x46, 0xa9, 0xde, 0xac, 0xd8,
+    0xa8, 0xfa, 0x76, 0x3f, 0xe3, 0x9c, 0x34, 0x3f, 0xf9, 0xdc, 0xbb, 0xc7, 0xc7, 0x0b, 0x4f, 0x1d,
+    0x8a, 0x51, 0xe0, 0x4b, 0xcd, 0xb4, 0x59, 0x31, 0xc8, 0x9f, 0x7e, 0xc9, 0xd9, 0x78, 0x73, 0x64,
+    0xea, 0xc5, 0xac, 0x83, 0x34, 0xd3, 0xeb, 0xc3, 0xc5, 0x81, 0xa0, 0xff, 0xfa, 0x13, 0x63, 0xeb,
+    0x17, 0x0d, 0xdd, 0x51, 0xb7, 0xf0, 0xda, 0x49, 0xd3, 0x16, 0x55, 0x26, 0x29, 0xd4, 0x68, 0x9e,
+    0x2b, 0x16, 0xbe, 0x58, 0x7d, 0x47, 0xa1, 0xfc, 0x8f, 0xf8, 0xb8, 0xd1, 0x7a, 0xd0, 0x31, 0xce,
+    0x45, 0xcb, 0x3a, 0x8f, 0x95, 0x16, 0x04, 0x28, 0xaf, 0xd7, 0xfb, 0xca, 0xbb, 0x4b, 0x40, 0x7e,
+];
+
+const INIT_ACC: [u64; ACC_NB] = [
+    PRIME32_3, PRIME64_1, PRIME64_2, PRIME64_3, PRIME64_4, PRIME32_2, PRIME64_5, PRIME32_1,
+];
+
+#[inline(always)]
+fn read_u32(input: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(input[pos..pos + 4].try_into().unwrap())
+}
+
+#[inline(always)]
+fn read_u64(input: &[u8], pos: usize) -> u64 {
+    u64::from_le_bytes(input[pos..pos + 8].try_into().unwrap())
+}
+
+#[inline(always)]
+fn mul128(a: u64, b: u64) -> (u64, u64) {
+    let product = a as u128 * b as u128;
+    (product as u64, (product >> 64) as u64)
+}
+
+#[inline(always)]
+fn mul128_fold64(a: u64, b: u64) -> u64 {
+    let (lo, hi) = mul128(a, b);
+    lo ^ hi
+}
+
+#[inline]
+fn avalanche(mut hash: u64) -> u64 {
+    hash ^= hash >> 37;
+    hash = hash.wrapping_mul(PRIME_MX1);
+    hash ^ (hash >> 32)
+}
+
+#[inline]
+fn rrmxmx(mut hash: u64, len: usize) -> u64 {
+    hash ^= hash.rotate_left(49) ^ hash.rotate_left(24);
+    hash = hash.wrapping_mul(PRIME_MX2);
+    hash ^= (hash >> 35).wrapping_add(len as u64);
+    hash = hash.wrapping_mul(PRIME_MX2);
+    hash ^ (hash >> 28)
+}
+
+#[inline(always)]
+fn mix16(input: &[u8], pos: usize, secret: &[u8], sec: usize, seed: u64) -> u64 {
+    let lo = read_u64(input, pos) ^ read_u64(secret, sec).wrapping_add(seed);
+    let hi = read_u64(input, pos + 8) ^ read_u64(secret, sec + 8).wrapping_sub(seed);
+    mul128_fold64(lo, hi)
+}
+
+/// Derives the secret used for long inputs with a non-zero seed.
+fn derive_secret(seed: u64) -> [u8; SECRET_SIZE] {
+    let mut secret = DEFAULT_SECRET;
+    for chunk in secret.chunks_exact_mut(16) {
+        let lo = read_u64(chunk, 0).wrapping_add(seed);
+        let hi = read_u64(chunk, 8).wrapping_sub(seed);
+        chunk[..8].copy_from_slice(&lo.to_le_bytes());
+        chunk[8..].copy_from_slice(&hi.to_le_bytes());
+    }
+    secret
+}
+
+fn hash64_0to16(input: &[u8], secret: &[u8], seed: u64) -> u64 {
+    let len = input.len();
+    if len > 8 {
+        let bitflip1 = (read_u64(secret, 24) ^ read_u64(secret, 32)).wrapping_add(seed);
+        let bitflip2 = (read_u64(secret, 40) ^ read_u64(secret, 48)).wrapping_sub(seed);
+        let lo = read_u64(input, 0) ^ bitflip1;
+        let hi = read_u64(input, len - 8) ^ bitflip2;
+        let acc = (len as u64)
+            .wrapping_add(lo.swap_bytes())
+            .wrapping_add(hi)
+            .wrapping_add(mul128_fold64(lo, hi));
+        avalanche(acc)
+    } else if len >= 4 {
+        let seed = seed ^ ((seed as u32).swap_bytes() as u64) << 32;
+        let input1 = read_u32(input, 0) as u64;
+        let input2 = read_u32(input, len - 4) as u64;
+        let bitflip = (read_u64(secret, 8) ^ read_u64(secret, 16)).wrapping_sub(seed);
+        rrmxmx((input2 | (input1 << 32)) ^ bitflip, len)
+    } else if len > 0 {
+        let combined = ((input[0] as u32) << 16)
+            | ((input[len >> 1] as u32) << 24)
+            | (input[len - 1] as u32)
+            | ((len as u32) << 8);
+        let bitflip = ((read_u32(secret, 0) ^ read_u32(secret, 4)) as u64).wrapping_add(seed);
+        xxh64_avalanche(combined as u64 ^ bitflip)
+    } else {
+        xxh64_avalanche(seed ^ read_u64(secret, 56) ^ read_u64(secret, 64))
+    }
+}
+
+fn hash64_17to128(input: &[u8], secret: &[u8], seed: u64) -> u64 {
+    let len = input.len();
+    let mut acc = (len as u64).wrapping_mul(PRIME64_1);
+    let rounds = (len - 1) / 32;
+    for i in (0..=rounds).rev() {
+        acc = acc.wrapping_add(mix16(input, 16 * i, secret, 32 * i, seed));
+        acc = acc.wrapping_add(mix16(input, len - 16 * (i + 1), secret, 32 * i + 16, seed));
+    }
+    avalanche(acc)
+}
+
+fn hash64_129to240(input: &[u8], secret: &[u8], seed: u64) -> u64 {
+    let len = input.len();
+    let mut acc = (len as u64).wrapping_mul(PRIME64_1);
+    for i in 0..8 {
+        acc = acc.wrapping_add(mix16(input, 16 * i, secret, 16 * i, seed));
+    }
+    acc = avalanche(acc);
+    for i in 8..len / 16 {
+        let sec = 16 * (i - 8) + MIDSIZE_STARTOFFSET;
+        acc = acc.wrapping_add(mix16(input, 16 * i, secret, sec, seed));
+    }
+    let sec = 136 - MIDSIZE_LASTOFFSET;
+    acc = acc.wrapping_add(mix16(input, len - 16, secret, sec, seed));
+    avalanche(acc)
+}
+
+fn hash64_short(input: &[u8], seed: u64) -> u64 {
+    let secret = &DEFAULT_SECRET[..];
+    match input.len() {
+        0..=16 => hash64_0to16(input, secret, seed),
+        17..=128 => hash64_17to128(input, secret, seed),
+        _ => hash64_129to240(input, secret, seed),
+    }
+}
+
+#[inline(always)]
+fn mix32(
+    acc: (u64, u64),
+    input: &[u8],
+    pos1: usize,
+    pos2: usize,
+    secret: &[u8],
+    sec: usize,
+    seed: u64,
+) -> (u64, u64) {
+    let mut lo = acc.0.wrapping_add(mix16(input, pos1, secret, sec, seed));
+    lo ^= read_u64(input, pos2).wrapping_add(read_u64(input, pos2 + 8));
+    let mut hi = acc
+        .1
+        .wrapping_add(mix16(input, pos2, secret, sec + 16, seed));
+    hi ^= read_u64(input, pos1).wrapping_add(read_u64(input, pos1 + 8));
+    (lo, hi)
+}
+
+fn to_u128(lo: u64, hi: u64) -> u128 {
+    (hi as u128) << 64 | lo as u128
+}
+
+fn hash128_0to16(input: &[u8], secret: &[u8], seed: u64) -> u128 {
+    let len = input.len();
+    if len > 8 {
+        let bitflipl = (read_u64(secret, 32) ^ read_u64(secret, 40)).wrapping_sub(seed);
+        let bitfliph = (read_u64(secret, 48) ^ read_u64(secret, 56)).wrapping_add(seed);
+        let input_lo = read_u64(input, 0);
+        let mut input_hi = read_u64(input, len - 8);
+        let (mut m_lo, mut m_hi) = mul128(input_lo ^ input_hi ^ bitflipl, PRIME64_1);
+        m_lo = m_lo.wrapping_add(((len - 1) as u64) << 54);
+        input_hi ^= bitfliph;
+        m_hi = m_hi
+            .wrapping_add(input_hi)
+            .wrapping_add((input_hi as u32 as u64).wrapping_mul(PRIME32_2 - 1));
+        m_lo ^= m_hi.swap_bytes();
+        let (h_lo, h_hi) = mul128(m_lo, PRIME64_2);
+        let h_hi = h_hi.wrapping_add(m_hi.wrapping_mul(PRIME64_2));
+        to_u128(avalanche(h_lo), avalanche(h_hi))
+    } else if len >= 4 {
+        let seed = seed ^ ((seed as u32).swap_bytes() as u64) << 32;
+        let input_lo = read_u32(input, 0) as u64;
+        let input_hi = read_u32(input, len - 4) as u64;
+        let bitflip = (read_u64(secret, 16) ^ read_u64(secret, 24)).wrapping_add(seed);
+        let keyed = (input_lo + (input_hi << 32)) ^ bitflip;
+        let (mut lo, mut hi) = mul128(keyed, PRIME64_1.wrapping_add((len as u64) << 2));
+        hi = hi.wrapping_add(lo << 1);
+        lo ^= hi >> 3;
+        lo ^= lo >> 35;
+        lo = lo.wrapping_mul(PRIME_MX2);
+        lo ^= lo >> 28;
+        to_u128(lo, avalanche(hi))
+    } else if len > 0 {
+        let combinedl = ((input[0] as u32) << 16)
+            | ((input[len >> 1] as u32) << 24)
+            | (input[len - 1] as u32)
+            | ((len as u32) << 8);
+        let combinedh = combinedl.swap_bytes().rotate_left(13);
+        let bitflipl = ((read_u32(secret, 0) ^ read_u32(secret, 4)) as u64).wrapping_add(seed);
+        let bitfliph = ((read_u32(secret, 8) ^ read_u32(secret, 12)) as u64).wrapping_sub(seed);
+        to_u128(
+            xxh64_avalanche(combinedl as u64 ^ bitflipl),
+            xxh64_avalanche(combinedh as u64 ^ bitfliph),
+        )
+    } else {
+        let bitflipl = read_u64(secret, 64) ^ read_u64(secret, 72);
+        let bitfliph = read_u64(secret, 80) ^ read_u64(secret, 88);
+        to_u128(
+            xxh64_avalanche(seed ^ bitflipl),
+            xxh64_avalanche(seed ^ bitfliph),
+        )
+    }
+}
+
+fn finish128_mid(acc: (u64, u64), len: usize, seed: u64) -> u128 {
+    let lo = acc.0.wrapping_add(acc.1);
+    let hi = acc
+        .0
+        .wrapping_mul(PRIME64_1)
+        .wrapping_add(acc.1.wrapping_mul(PRIME64_4))
+        .wrapping_add((len as u64).wrapping_sub(seed).wrapping_mul(PRIME64_2));
+    to_u128(avalanche(lo), avalanche(hi).wrapping_neg())
+}
+
+fn hash128_17to128(input: &[u8], secret: &[u8], seed: u64) -> u128 {
+    let len = input.len();
+    let mut acc = ((len as u64).wrapping_mul(PRIME64_1), 0);
+    let rounds = (len - 1) / 32;
+    for i in (0..=rounds).rev() {
+        acc = mix32(acc, input, 16 * i, len - 16 * (i + 1), secret, 32 * i, seed);
+    }
+    finish128_mid(acc, len, seed)
+}
+
+fn hash128_129to240(input: &[u8], secret: &[u8], seed: u64) -> u128 {
+    let len = input.len();
+    let mut acc = ((len as u64).wrapping_mul(PRIME64_1), 0);
+    for i in 0..4 {
+        acc = mix32(acc, input, 32 * i, 32 * i + 16, secret, 32 * i, seed);
+    }
+    acc = (avalanche(acc.0), avalanche(acc.1));
+    for i in 4..len / 32 {
+        let sec = MIDSIZE_STARTOFFSET + 32 * (i - 4);
+        acc = mix32(acc, input, 32 * i, 32 * i + 16, secret, sec, seed);
+    }
+    let sec = 136 - MIDSIZE_LASTOFFSET - 16;
+    acc = mix32(
+        acc,
+        input,
+        len - 16,
+        len - 32,
+        secret,
+        sec,
+        seed.wrapping_neg(),
+    );
+    finish128_mid(acc, len, seed)
+}
+
+fn hash128_short(input: &[u8], seed: u64) -> u128 {
+    let secret = &DEFAULT_SECRET[..];
+    match input.len() {
+        0..=16 => hash128_0to16(input, secret, seed),
+        17..=128 => hash128_17to128(input, secret, seed),
+        _ => hash128_129to240(input, secret, seed),
+    }
+}
+
+/// The stripe accumulator used for inputs longer than [`MIDSIZE_MAX`].
+mod accumulate {
+    #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+    use super::read_u64;
+    use super::{ACC_NB, PRIME32_1, SECRET_CONSUME_RATE, STRIPE_LEN};
+
+    /// Mixes one 64 byte stripe into the accumulators.
+    #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+    #[inline(always)]
+    pub(super) fn accumulate_512(acc: &mut [u64; ACC_NB], stripe: &[u8], secret: &[u8]) {
+        for i in 0..ACC_NB {
+            let data = read_u64(stripe, 8 * i);
+            let key = data ^ read_u64(secret, 8 * i);
+            acc[i ^ 1] = acc[i ^ 1].wrapping_add(data);
+            acc[i] = acc[i].wrapping_add((key & 0xFFFF_FFFF).wrapping_mul(key >> 32));
+        }
+    }
+
+    /// Scrambles the accumulators at the end of a block.
+    #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+    #[inline(always)]
+    pub(super) fn scramble(acc: &mut [u64; ACC_NB], secret: &[u8]) {
+        for (i, acc) in acc.iter_mut().enumerate() {
+            let mut a = *acc;
+            a ^= a >> 47;
+            a ^= read_u64(secret, 8 * i);
+            *acc = a.wrapping_mul(PRIME32_1);
+        }
+    }
+
+    /// wasm SIMD128 version, two accumulators per vector.
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    #[inline(always)]
+    pub(super) fn accumulate_512(acc: &mut [u64; ACC_NB], stripe: &[u8], secret: &[u8]) {
+        use core::arch::wasm32::*;
+
+        let stripe = &stripe[..STRIPE_LEN];
+        let secret = &secret[..STRIPE_LEN];
+        for i in 0..ACC_NB / 2 {
+            // SAFETY: all reads and writes are in bounds of the checked slices above.
+            unsafe {
+                let data = v128_load(stripe.as_ptr().add(16 * i) as *const v128);
+                let key = v128_xor(data, v128_load(secret.as_ptr().add(16 * i) as *const v128));
+                // Low halves times high halves of each 64-bit lane.
+                let lo = i32x4_shuffle::<0, 2, 0, 2>(key, key);
+                let hi = i32x4_shuffle::<1, 3, 1, 3>(key, key);
+                let product = u64x2_extmul_low_u32x4(lo, hi);
+                let swapped = i64x2_shuffle::<1, 0>(data, data);
+                let lanes = acc.as_mut_ptr().add(2 * i) as *mut v128;
+                let sum = i64x2_add(v128_load(lanes), i64x2_add(product, swapped));
+                v128_store(lanes, sum);
+            }
+        }
+    }
+
+    /// wasm SIMD128 version, two accumulators per vector.
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    #[inline(always)]
+    pub(super) fn scramble(acc: &mut [u64; ACC_NB], secret: &[u8]) {
+        use core::arch::wasm32::*;
+
+        let secret = &secret[..STRIPE_LEN];
+        let prime = u64x2_splat(PRIME32_1);
+        for i in 0..ACC_NB / 2 {
+            // SAFETY: all reads and writes are in bounds of the checked slice above.
+            unsafe {
+                let lanes = acc.as_mut_ptr().add(2 * i) as *mut v128;
+                let mut a = v128_load(lanes);
+                a = v128_xor(a, u64x2_shr(a, 47));
+                a = v128_xor(a, v128_load(secret.as_ptr().add(16 * i) as *const v128));
+                v128_store(lanes, i64x2_mul(a, prime));
+            }
+        }
+    }
+
+    /// Mixes `stripes` consecutive stripes, advancing through the secret by
+    /// [`SECRET_CONSUME_RATE`] bytes per stripe.
+    #[inline]
+    pub(super) fn accumulate(acc: &mut [u64; ACC_NB], input: &[u8], secret: &[u8], stripes: usize) {
+        for n in 0..stripes {
+            accumulate_512(
+                acc,
+                &input[n * STRIPE_LEN..],
+                &secret[n * SECRET_CONSUME_RATE..],
+            );
+        }
+    }
+}
+
+use accumulate::{accumulate, accumulate_512, scramble};
+
+/// Runs a whole input longer than [`MIDSIZE_MAX`] through the accumulators.
+fn hash_long(input: &[u8], secret: &[u8; SECRET_SIZE]) -> [u64; ACC_NB] {
+    let mut acc = INIT_ACC;
+    let len = input.len();
+    let block_len = STRIPE_LEN * STRIPES_PER_BLOCK;
+    let blocks = (len - 1) / block_len;
+    for n in 0..blocks {
+        accumulate(&mut acc, &input[n * block_len..], secret, STRIPES_PER_BLOCK);
+        scramble(&mut acc, &secret[SECRET_SIZE - STRIPE_LEN..]);
+    }
+
+    let stripes = ((len - 1) - block_len * blocks) / STRIPE_LEN;
+    accumulate(&mut acc, &input[blocks * block_len..], secret, stripes);
+
+    let last_secret = SECRET_SIZE - STRIPE_LEN - SECRET_LASTACC_START;
+    accumulate_512(&mut acc, &input[len - STRIPE_LEN..], &secret[last_secret..]);
+    acc
+}
+
+fn merge_accs(acc: &[u64; ACC_NB], secret: &[u8], start: u64) -> u64 {
+    let mut result = start;
+    for i in 0..4 {
+        result = result.wrapping_add(mul128_fold64(
+            acc[2 * i] ^ read_u64(secret, 16 * i),
+            acc[2 * i + 1] ^ read_u64(secret, 16 * i + 8),
+        ));
+    }
+    avalanche(result)
+}
+
+fn finish64_long(acc: &[u64; ACC_NB], secret: &[u8; SECRET_SIZE], len: u64) -> u64 {
+    merge_accs(
+        acc,
+        &secret[SECRET_MERGEACCS_START..],
+        len.wrapping_mul(PRIME64_1),
+    )
+}
+
+fn finish128_long(acc: &[u64; ACC_NB], secret: &[u8; SECRET_SIZE], len: u64) -> u128 {
+    let lo = finish64_long(acc, secret, len);
+    let hi = merge_accs(
+        acc,
+        &secret[SECRET_SIZE - STRIPE_LEN - SECRET_MERGEACCS_START..],
+        !len.wrapping_mul(PRIME64_2),
+    );
+    to_u128(lo, hi)
+}
+
+fn secret_for_seed(seed: u64) -> [u8; SECRET_SIZE] {
+    if seed == 0 {
+        DEFAULT_SECRET
+    } else {
+        derive_secret(seed)
+    }
+}
+
+/// Streaming state shared by the 64 and 128 bit variants.
+#[derive(Clone)]
+struct State {
+    seed: u64,
+    secret: [u8; SECRET_SIZE],
+    acc: [u64; ACC_NB],
+    buffer: [u8; BUFFER_SIZE],
+    buffered: usize,
+    stripes_so_far: usize,
+    total_len: u64,
+}
+
+impl State {
+    fn with_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            secret: secret_for_seed(seed),
+            acc: INIT_ACC,
+            buffer: [0; BUFFER_SIZE],
+            buffered: 0,
+            stripes_so_far: 0,
+            total_len: 0,
+        }
+    }
+
+    /// Accumulates `stripes` stripes of `input`, scrambling whenever a block is complete.
+    fn consume_stripes(
+        acc: &mut [u64; ACC_NB],
+        stripes_so_far: &mut usize,
+        input: &[u8],
+        stripes: usize,
+        secret: &[u8; SECRET_SIZE],
+    ) {
+        let to_block_end = STRIPES_PER_BLOCK - *stripes_so_far;
+        if stripes >= to_block_end {
+            let secret_pos = *stripes_so_far * SECRET_CONSUME_RATE;
+            accumulate(acc, input, &secret[secret_pos..], to_block_end);
+            scramble(acc, &secret[SECRET_SIZE - STRIPE_LEN..]);
+            let after = stripes - to_block_end;
+            accumulate(acc, &input[to_block_end * STRIPE_LEN..], secret, after);
+            *stripes_so_far = after;
+        } else {
+            let secret_pos = *stripes_so_far * SECRET_CONSUME_RATE;
+            accumulate(acc, input, &secret[secret_pos..], stripes);
+            *stripes_so_far += stripes;
+        }
+    }
+
+    fn write(&mut self, mut input: &[u8]) {
+        self.total_len = self.total_len.wrapping_add(input.len() as u64);
+
+        if input.len() <= BUFFER_SIZE - self.buffered {
+            self.buffer[self.buffered..self.buffered + input.len()].copy_from_slice(input);
+            self.buffered += input.len();
+            return;
+        }
+
+        if self.buffered > 0 {
+            let fill = BUFFER_SIZE - self.buffered;
+            self.buffer[self.buffered..].copy_from_slice(&input[..fill]);
+            input = &input[fill..];
+            Self::consume_stripes(
+                &mut self.acc,
+                &mut self.stripes_so_far,
+                &self.buffer,
+                BUFFER_STRIPES,
+                &self.secret,
+            );
+            self.buffered = 0;
+        }
+
+        // Always keep at least one byte buffered, the digest needs the last stripe.
+        if input.len() > BUFFER_SIZE {
+            let mut consumed = 0;
+            while input.len() - consumed > BUFFER_SIZE {
+                Self::consume_stripes(
+                    &mut self.acc,
+                    &mut self.stripes_so_far,
+                    &input[consumed..],
+                    BUFFER_STRIPES,
+                    &self.secret,
+                );
+                consumed += BUFFER_SIZE;
+            }
+            // Keep the last consumed stripe in case the tail is shorter than a stripe.
+            self.buffer[BUFFER_SIZE - STRIPE_LEN..]
+                .copy_from_slice(&input[consumed - STRIPE_LEN..consumed]);
+            input = &input[consumed..];
+        }
+
+        self.buffer[..input.len()].copy_from_slice(input);
+        self.buffered = input.len();
+    }
+
+    /// Returns the accumulators after the buffered tail and the last stripe are mixed in.
+    fn digest_long(&self) -> [u64; ACC_NB] {
+        let mut acc = self.acc;
+        let mut last_stripe = [0u8; STRIPE_LEN];
+        let last: &[u8] = if self.buffered >= STRIPE_LEN {
+            let stripes = (self.buffered - 1) / STRIPE_LEN;
+            let mut stripes_so_far = self.stripes_so_far;
+            Self::consume_stripes(
+                &mut acc,
+                &mut stripes_so_far,
+                &self.buffer,
+                stripes,
+                &self.secret,
+            );
+            &self.buffer[self.buffered - STRIPE_LEN..self.buffered]
+        } else {
+            let catchup = STRIPE_LEN - self.buffered;
+            last_stripe[..catchup].copy_from_slice(&self.buffer[BUFFER_SIZE - catchup..]);
+            last_stripe[catchup..].copy_from_slice(&self.buffer[..self.buffered]);
+            &last_stripe
+        };
+        let last_secret = SECRET_SIZE - STRIPE_LEN - SECRET_LASTACC_START;
+        accumulate_512(&mut acc, last, &self.secret[last_secret..]);
+        acc
+    }
+
+    fn is_short(&self) -> bool {
+        self.total_len <= MIDSIZE_MAX as u64
+    }
+
+    fn short_input(&self) -> &[u8] {
+        &self.buffer[..self.buffered]
+    }
+}
+
+/// Streaming XXH3 64-bit state.
+#[derive(Clone)]
+pub struct XxHash3_64 {
+    state: State,
+}
+
+impl XxHash3_64 {
+    /// Create a hasher with the provided seed.
+    #[inline]
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            state: State::with_seed(seed),
+        }
+    }
+
+    /// Hash a full buffer in one shot.
+    pub fn oneshot(seed: u64, data: &[u8]) -> u64 {
+        if data.len() <= MIDSIZE_MAX {
+            hash64_short(data, seed)
+        } else {
+            let secret = secret_for_seed(seed);
+            finish64_long(&hash_long(data, &secret), &secret, data.len() as u64)
+        }
+    }
+
+    /// Finalize and return the 64-bit digest.
+    pub fn finish_64(&self) -> u64 {
+        let state = &self.state;
+        if state.is_short() {
+            hash64_short(state.short_input(), state.seed)
+        } else {
+            finish64_long(&state.digest_long(), &state.secret, state.total_len)
+        }
+    }
+}
+
+impl Default for XxHash3_64 {
+    fn default() -> Self {
+        Self::with_seed(0)
+    }
+}
+
+impl Hasher for XxHash3_64 {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.finish_64()
+    }
+
+    #[inline]
+    fn write(&mut self, data: &[u8]) {
+        self.state.write(data);
+    }
+}
+
+/// Streaming XXH3 128-bit state.
+#[derive(Clone)]
+pub struct XxHash3_128 {
+    state: State,
+}
+
+impl XxHash3_128 {
+    /// Create a hasher with the provided seed.
+    #[inline]
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            state: State::with_seed(seed),
+        }
+    }
+
+    /// Hash a full buffer in one shot.
+    pub fn oneshot(seed: u64, data: &[u8]) -> u128 {
+        if data.len() <= MIDSIZE_MAX {
+            hash128_short(data, seed)
+        } else {
+            let secret = secret_for_seed(seed);
+            finish128_long(&hash_long(data, &secret), &secret, data.len() as u64)
+        }
+    }
+
+    /// Feed more data into the hasher.
+    #[inline]
+    pub fn write(&mut self, data: &[u8]) {
+        self.state.write(data);
+    }
+
+    /// Finalize and return the 128-bit digest.
+    pub fn finish_128(&self) -> u128 {
+        let state = &self.state;
+        if state.is_short() {
+            hash128_short(state.short_input(), state.seed)
+        } else {
+            finish128_long(&state.digest_long(), &state.secret, state.total_len)
+        }
+    }
+}
+
+impl Default for XxHash3_128 {
+    fn default() -> Self {
+        Self::with_seed(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::hash::Hasher;
+
+    use super::{XxHash3_128, XxHash3_64};
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 29 + (i >> 5)) as u8).collect()
+    }
+
+    #[test]
+    fn known_vector_empty() {
+        assert_eq!(XxHash3_64::oneshot(0, b""), 0x2d06_8005_38d3_94c2);
+    }
+
+    #[test]
+    fn oneshot_matches_twox_hash_across_size_classes() {
+        let data = sample(4096);
+        for len in (0..300).chain([1023, 1024, 1025, 2048, 4096]) {
+            for seed in [0, 0x1234_5678_9abc_def0] {
+                let input = &data[..len];
+                assert_eq!(
+                    XxHash3_64::oneshot(seed, input),
+                    twox_hash::XxHash3_64::oneshot_with_seed(seed, input),
+                    "64 len={len} seed={seed}"
+                );
+                assert_eq!(
+                    XxHash3_128::oneshot(seed, input),
+                    twox_hash::XxHash3_128::oneshot_with_seed(seed, input),
+                    "128 len={len} seed={seed}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn streaming_matches_oneshot() {
+        let data = sample(5000);
+        for len in [0, 100, 240, 241, 256, 257, 320, 1024, 1100, 5000] {
+            for step in [1, 7, 64, 100, 256, 300, 5000] {
+                let mut h64 = XxHash3_64::with_seed(7);
+                let mut h128 = XxHash3_128::with_seed(7);
+                for chunk in data[..len].chunks(step) {
+                    h64.write(chunk);
+                    h128.write(chunk);
+                }
+                let input = &data[..len];
+                assert_eq!(
+                    h64.finish(),
+                    XxHash3_64::oneshot(7, input),
+                    "len={len} step={step}"
+                );
+                assert_eq!(h128.finish_128(), XxHash3_128::oneshot(7, input));
+            }
+        }
+    }
+}
@@ -15,6 +15,18 @@ extern crate alloc;
 #[macro_use]
 extern crate more_asserts;
 
+/// Checks for an optional x86_64 CPU feature, at runtime when `std` is available.
+#[cfg(target_arch = "x86_64")]
+macro_rules! has_x86_feature {
+    ($feature:tt) => {{
+        #[cfg(feature = "std")]
+        let detected = std::is_x86_feature_detected!($feature);
+        #[cfg(not(feature = "std"))]
+        let detected = cfg!(target_feature = $feature);
+        detected
+    }};
+}
+
 pub mod hash;
 
 #[cfg(feature = "block")]
@@ -40,8 +52,11 @@ mod fastcpy_unsafe;
         feature = "wasm-simd",
         target_arch = "wasm32",
         target_feature = "simd128"
-    )
+    ),
+    target_arch = "x86_64",
+    target_arch = "aarch64"
 ))]
+#[cfg_attr(not(any(feature = "block", feature = "frame")), allow(dead_code))]
 pub mod simd;
 
 #[cfg_attr(
@@ -1,10 +1,22 @@
 // Source provenance: derived from https://github.com/pseitz/lz4_flex (MIT), commit 975bfa7ac9583da879b5d7578b423232d84f69fe.
 //! SIMD optimizations for LZ4 compression/decompression
 //!
-//! This module provides WASM SIMD128 optimized versions of hot path operations.
+//! This module provides SIMD optimized versions of hot path operations:
+//! - WASM SIMD128 on `wasm32` builds with `simd128` enabled.
+//! - SSE2 (baseline), SSSE3 and AVX2 on `x86_64`. The optional extensions are detected at
+//!   runtime when `std` is enabled, otherwise only the ones enabled at compile time are used.
+//! - NEON on `aarch64`.
+//!
+//! The match counting entry points are public on every target. SIMD128 wasm builds also
+//! export the `wild_copy_16_simd`, `copy_16_simd`, `copy_32_simd` and `duplicate_pattern_simd`
+//! copy helpers; the per-ISA backends and the decoder's copy kernels are internal to the crate.
 
+#[cfg(target_arch = "aarch64")]
+use core::arch::aarch64::*;
 #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
 use core::arch::wasm32::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
 
 /// Count matching bytes between two slices using SIMD.
 /// Returns the number of bytes that match from the start.
@@ -90,10 +102,20 @@ pub fn count_same_bytes_scalar(a: &[u8], b: &[u8]) -> usize {
     offset
 }
 
+/// Scalar fallback for counting the leading bytes equal to `byte`.
+#[cfg(all(target_arch = "x86_64", not(feature = "safe-encode")))]
+#[inline]
+pub(crate) fn count_run_scalar(a: &[u8], byte: u8) -> usize {
+    a.iter().take_while(|&&b| b == byte).count()
+}
+
 /// Wild copy: copy `len` bytes from src to dst, potentially overwriting up to 15 extra bytes.
 /// This is safe when the caller ensures dst has at least len + 15 bytes available.
 ///
 /// Uses SIMD for 16-byte copies.
+///
+/// # Safety
+/// `src` must be valid for reads and `dst` for writes of `len` rounded up to a multiple of 16.
 #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
 #[inline]
 pub unsafe fn wild_copy_16_simd(src: *const u8, dst: *mut u8, len: usize) {
@@ -108,6 +130,9 @@ pub unsafe fn wild_copy_16_simd(src: *const u8, dst: *mut u8, len: usize) {
 }
 
 /// Copy exactly 16 bytes using SIMD
+///
+/// # Safety
+/// `src` must be valid for reads and `dst` for writes of 16 bytes.
 #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
 #[inline]
 pub unsafe fn copy_16_simd(src: *const u8, dst: *mut u8) {
@@ -116,6 +141,9 @@ pub unsafe fn copy_16_simd(src: *const u8, dst: *mut u8) {
 }
 
 /// Copy exactly 32 bytes using SIMD (2x 16-byte copies)
+///
+/// # Safety
+/// `src` must be valid for reads and `dst` for writes of 32 bytes.
 #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
 #[inline]
 pub unsafe fn copy_32_simd(src: *const u8, dst: *mut u8) {
@@ -130,6 +158,10 @@ pub unsafe fn copy_32_simd(src: *const u8, dst: *mut u8) {
 /// For offset 2: duplicate 2-byte pattern
 /// For offset 4: duplicate 4-byte pattern
 /// For offset 8: duplicate 8-byte pattern
+///
+/// # Safety
+/// `src` must be valid for reads of `offset` bytes (`len` for other offsets) and `dst` for
+/// writes of `len` rounded up to a multiple of 16.
 #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
 #[inline]
 pub unsafe fn duplicate_pattern_simd(dst: *mut u8, src: *const u8, offset: usize, len: usize) {
@@ -182,9 +214,325 @@ pub unsafe fn duplicate_pattern_simd(dst: *mut u8, src: *const u8, offset: usize
     }
 }
 
+/// Count matching bytes between two slices using SIMD.
+/// Returns the number of bytes that match from the start.
+///
+/// Uses AVX2 to compare 32 bytes at a time when the CPU supports it and SSE2 otherwise.
+#[cfg(target_arch = "x86_64")]
+#[inline]
+pub fn count_same_bytes_simd(a: &[u8], b: &[u8]) -> usize {
+    if a.len().min(b.len()) >= 32 && has_x86_feature!("avx2") {
+        // SAFETY: AVX2 support was checked above.
+        return unsafe { count_same_bytes_avx2(a, b) };
+    }
+    count_same_bytes_sse2(a, b)
+}
+
+/// SSE2 version of [`count_same_bytes_simd`], comparing 16 bytes at a time.
+#[cfg(target_arch = "x86_64")]
+#[inline]
+pub(crate) fn count_same_bytes_sse2(a: &[u8], b: &[u8]) -> usize {
+    let len = a.len().min(b.len());
+    let mut offset = 0;
+
+    while offset + 16 <= len {
+        // SAFETY: both loads are in bounds, SSE2 is part of the x86_64 baseline.
+        let mask = unsafe {
+            let va = _mm_loadu_si128(a.as_ptr().add(offset) as *const __m128i);
+            let vb = _mm_loadu_si128(b.as_ptr().add(offset) as *const __m128i);
+            _mm_movemask_epi8(_mm_cmpeq_epi8(va, vb)) as u32
+        };
+        if mask != 0xFFFF {
+            return offset + (!mask).trailing_zeros() as usize;
+        }
+        offset += 16;
+    }
+
+    offset + count_same_bytes_scalar(&a[offset..len], &b[offset..len])
+}
+
+/// AVX2 version of [`count_same_bytes_simd`], comparing 32 bytes at a time.
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn count_same_bytes_avx2(a: &[u8], b: &[u8]) -> usize {
+    let len = a.len().min(b.len());
+    let mut offset = 0;
+
+    while offset + 32 <= len {
+        let va = _mm256_loadu_si256(a.as_ptr().add(offset) as *const __m256i);
+        let vb = _mm256_loadu_si256(b.as_ptr().add(offset) as *const __m256i);
+        let mask = _mm256_movemask_epi8(_mm256_cmpeq_epi8(va, vb)) as u32;
+        if mask != u32::MAX {
+            return offset + (!mask).trailing_zeros() as usize;
+        }
+        offset += 32;
+    }
+
+    offset + count_same_bytes_sse2(&a[offset..len], &b[offset..len])
+}
+
+/// Count the leading bytes of `a` equal to `byte` using SIMD.
+///
+/// Uses AVX2 to compare 32 bytes at a time when the CPU supports it and SSE2 otherwise.
+#[cfg(all(target_arch = "x86_64", not(feature = "safe-encode")))]
+#[inline]
+pub(crate) fn count_run_simd(a: &[u8], byte: u8) -> usize {
+    if a.len() >= 32 && has_x86_feature!("avx2") {
+        // SAFETY: AVX2 support was checked above.
+        return unsafe { count_run_avx2(a, byte) };
+    }
+    count_run_sse2(a, byte)
+}
+
+/// SSE2 version of [`count_run_simd`], comparing 16 bytes at a time.
+#[cfg(all(target_arch = "x86_64", not(feature = "safe-encode")))]
+#[inline]
+pub(crate) fn count_run_sse2(a: &[u8], byte: u8) -> usize {
+    let mut offset = 0;
+
+    while offset + 16 <= a.len() {
+        // SAFETY: the load is in bounds, SSE2 is part of the x86_64 baseline.
+        let mask = unsafe {
+            let va = _mm_loadu_si128(a.as_ptr().add(offset) as *const __m128i);
+            _mm_movemask_epi8(_mm_cmpeq_epi8(va, _mm_set1_epi8(byte as i8))) as u32
+        };
+        if mask != 0xFFFF {
+            return offset + (!mask).trailing_zeros() as usize;
+        }
+        offset += 16;
+    }
+
+    offset + count_run_scalar(&a[offset..], byte)
+}
+
+/// AVX2 version of [`count_run_simd`], comparing 32 bytes at a time.
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[cfg(all(target_arch = "x86_64", not(feature = "safe-encode")))]
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn count_run_avx2(a: &[u8], byte: u8) -> usize {
+    let pattern = _mm256_set1_epi8(byte as i8);
+    let mut offset = 0;
+
+    while offset + 32 <= a.len() {
+        let va = _mm256_loadu_si256(a.as_ptr().add(offset) as *const __m256i);
+        let mask = _mm256_movemask_epi8(_mm256_cmpeq_epi8(va, pattern)) as u32;
+        if mask != u32::MAX {
+            return offset + (!mask).trailing_zeros() as usize;
+        }
+        offset += 32;
+    }
+
+    offset + count_run_sse2(&a[offset..], byte)
+}
+
+/// Count matching bytes between two slices using SIMD.
+/// Returns the number of bytes that match from the start.
+///
+/// This version uses NEON to compare 16 bytes at a time.
+#[cfg(target_arch = "aarch64")]
+#[inline]
+pub fn count_same_bytes_simd(a: &[u8], b: &[u8]) -> usize {
+    let len = a.len().min(b.len());
+    let mut offset = 0;
+
+    while offset + 16 <= len {
+        // SAFETY: both loads are in bounds, NEON is part of the aarch64 baseline.
+        let all_equal = unsafe {
+            let eq = vceqq_u8(
+                vld1q_u8(a.as_ptr().add(offset)),
+                vld1q_u8(b.as_ptr().add(offset)),
+            );
+            vminvq_u8(eq) == 0xFF
+        };
+        if !all_equal {
+            // Locate the mismatch inside this chunk, it is at most two word compares.
+            break;
+        }
+        offset += 16;
+    }
+
+    offset + count_same_bytes_scalar(&a[offset..len], &b[offset..len])
+}
+
+/// `PATTERN_INDICES[offset]` holds the swizzle indices `i % offset` that repeat the first
+/// `offset` lanes of a vector across all 16 lanes.
+#[cfg(any(
+    not(feature = "safe-decode"),
+    all(target_arch = "wasm32", target_feature = "simd128")
+))]
+const PATTERN_INDICES: [[u8; 16]; 16] = {
+    let mut table = [[0u8; 16]; 16];
+    let mut offset = 1;
+    while offset < 16 {
+        let mut i = 0;
+        while i < 16 {
+            table[offset][i] = (i % offset) as u8;
+            i += 1;
+        }
+        offset += 1;
+    }
+    table
+};
+
+/// Self-referential copy for overlapping matches with an offset in `1..16`.
+///
+/// Writes `len` bytes to `dst`, where every byte repeats the one `offset` bytes before it.
+/// The pattern is expanded to a full vector with a byte shuffle (`pshufb`, `tbl` or `swizzle`)
+/// and stored in steps that are a multiple of `offset`; writes never go past `dst + len`.
+///
+/// Returns `false` without writing anything if the CPU lacks a byte shuffle (SSSE3 on
+/// x86_64), so the caller can fall back to its scalar copy.
+///
+/// # Safety
+/// `dst - offset..dst + len` must be valid for reads and writes, and `offset` must be in
+/// `1..16`.
+#[cfg(not(feature = "safe-decode"))]
+#[inline]
+pub(crate) unsafe fn duplicate_overlapping_simd(dst: *mut u8, offset: usize, len: usize) -> bool {
+    debug_assert!((1..16).contains(&offset));
+    #[cfg(target_arch = "x86_64")]
+    {
+        if !has_x86_feature!("ssse3") {
+            return false;
+        }
+        duplicate_overlapping_ssse3(dst, offset, len);
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        let mut seed = [0u8; 16];
+        core::ptr::copy_nonoverlapping(dst.sub(offset), seed.as_mut_ptr(), offset);
+        let indices = PATTERN_INDICES[offset].as_ptr();
+        #[cfg(target_arch = "aarch64")]
+        let pattern = vqtbl1q_u8(vld1q_u8(seed.as_ptr()), vld1q_u8(indices));
+        #[cfg(target_arch = "wasm32")]
+        let pattern = i8x16_swizzle(
+            core::ptr::read_unaligned(seed.as_ptr() as *const v128),
+            core::ptr::read_unaligned(indices as *const v128),
+        );
+        store_pattern(dst, offset, len, |ptr| {
+            #[cfg(target_arch = "aarch64")]
+            vst1q_u8(ptr, pattern);
+            #[cfg(target_arch = "wasm32")]
+            core::ptr::write_unaligned(ptr as *mut v128, pattern);
+        });
+    }
+    true
+}
+
+#[cfg(all(target_arch = "x86_64", not(feature = "safe-decode")))]
+#[target_feature(enable = "ssse3")]
+unsafe fn duplicate_overlapping_ssse3(dst: *mut u8, offset: usize, len: usize) {
+    let mut seed = [0u8; 16];
+    core::ptr::copy_nonoverlapping(dst.sub(offset), seed.as_mut_ptr(), offset);
+    let pattern = _mm_shuffle_epi8(
+        _mm_loadu_si128(seed.as_ptr() as *const __m128i),
+        _mm_loadu_si128(PATTERN_INDICES[offset].as_ptr() as *const __m128i),
+    );
+    store_pattern(dst, offset, len, |ptr| {
+        _mm_storeu_si128(ptr as *mut __m128i, pattern)
+    });
+}
+
+/// Stores a 16-byte pattern of period `offset` with `store`, advancing by the largest multiple
+/// of `offset` that fits in a vector so every store starts at the same phase. The remaining
+/// tail is copied byte by byte.
+#[cfg(not(feature = "safe-decode"))]
+#[inline(always)]
+unsafe fn store_pattern(dst: *mut u8, offset: usize, len: usize, store: impl Fn(*mut u8)) {
+    let step = 16 - 16 % offset;
+    let mut written = 0;
+    while written + 16 <= len {
+        store(dst.add(written));
+        written += step;
+    }
+    while written < len {
+        *dst.add(written) = *dst.add(written).sub(offset);
+        written += 1;
+    }
+}
+
+/// Load 16 bytes from the start of `src` without `unsafe`.
+#[cfg(all(
+    target_arch = "wasm32",
+    target_feature = "simd128",
+    feature = "safe-decode"
+))]
+#[inline(always)]
+fn load_16_safe(src: &[u8]) -> v128 {
+    let lo = u64::from_le_bytes(src[..8].try_into().unwrap());
+    let hi = u64::from_le_bytes(src[8..16].try_into().unwrap());
+    u64x2(lo, hi)
+}
+
+/// Store 16 bytes to the start of `dst` without `unsafe`.
+#[cfg(all(
+    target_arch = "wasm32",
+    target_feature = "simd128",
+    feature = "safe-decode"
+))]
+#[inline(always)]
+fn store_16_safe(dst: &mut [u8], v: v128) {
+    dst[..8].copy_from_slice(&u64x2_extract_lane::<0>(v).to_le_bytes());
+    dst[8..16].copy_from_slice(&u64x2_extract_lane::<1>(v).to_le_bytes());
+}
+
+/// Bounds-checked self-referential copy used by the `safe-decode` sink.
+///
+/// Writes `len` bytes to `output[pos..]`, where every byte repeats the one `offset` bytes
+/// before it. Offsets below 16 are expanded into a 16-byte pattern with a swizzle and stored
+/// in steps that are a multiple of `offset`; larger offsets copy 16 already-decoded bytes per
+/// step. All accesses go through slices, so no `unsafe` is involved.
+///
+/// A zero `offset` (only produced by malformed input) leaves the bytes untouched, like the
+/// scalar loop it replaces.
+///
+/// # Panics
+/// Panics if `offset > pos` or `pos + len > output.len()`.
+#[cfg(all(
+    target_arch = "wasm32",
+    target_feature = "simd128",
+    feature = "safe-decode"
+))]
+#[inline]
+pub(crate) fn duplicate_overlapping_safe(output: &mut [u8], pos: usize, offset: usize, len: usize) {
+    assert!(offset <= pos);
+    let end = pos + len;
+    assert!(end <= output.len());
+
+    let mut dst = pos;
+    if offset >= 16 {
+        while dst + 16 <= end {
+            let v = load_16_safe(&output[dst - offset..]);
+            store_16_safe(&mut output[dst..], v);
+            dst += 16;
+        }
+    } else if offset != 0 && len >= 16 {
+        let mut seed = [0u8; 16];
+        seed[..offset].copy_from_slice(&output[pos - offset..pos]);
+        let pattern = i8x16_swizzle(load_16_safe(&seed), load_16_safe(&PATTERN_INDICES[offset]));
+        // Advance by the largest multiple of `offset` that fits in a vector, so every store
+        // starts at the same phase of the pattern.
+        let step = 16 - 16 % offset;
+        while dst + 16 <= end {
+            store_16_safe(&mut output[dst..], pattern);
+            dst += step;
+        }
+    }
+
+    for i in dst..end {
+        output[i] = output[i - offset];
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec::Vec;
 
     #[test]
     fn test_count_same_bytes_scalar() {
@@ -224,4 +572,123 @@ mod tests {
         b[0] = 1;
         assert_eq!(count_same_bytes_simd(&a, &b), 0);
     }
+
+    /// Every mismatch position for every length up to 80, against the scalar count.
+    fn check_count_same_bytes(count: impl Fn(&[u8], &[u8]) -> usize) {
+        for len in 0..80usize {
+            let a: Vec<u8> = (0..len).map(|i| (i * 7 + 3) as u8).collect();
+            assert_eq!(count(&a, &a), len, "len={len}");
+            for mismatch in 0..len {
+                let mut b = a.clone();
+                b[mismatch] ^= 0x80;
+                assert_eq!(count(&a, &b), count_same_bytes_scalar(&a, &b));
+                assert_eq!(count(&a, &b), mismatch, "len={len} mismatch={mismatch}");
+                // Unequal lengths are bounded by the shorter slice.
+                assert_eq!(count(&a[..mismatch], &b), mismatch);
+            }
+        }
+    }
+
+    #[test]
+    fn test_count_same_bytes_simd_parity() {
+        check_count_same_bytes(count_same_bytes_simd);
+    }
+
+    /// Every run length up to 80, against the scalar count.
+    #[cfg(all(target_arch = "x86_64", not(feature = "safe-encode")))]
+    fn check_count_run(count: impl Fn(&[u8], u8) -> usize) {
+        for len in 0..80usize {
+            for byte in [0u8, 0x80, 0xFF] {
+                let run = vec![byte; len];
+                assert_eq!(count(&run, byte), len, "len={len}");
+                for end in 0..len {
+                    let mut a = run.clone();
+                    a[end] ^= 0x01;
+                    assert_eq!(count(&a, byte), count_run_scalar(&a, byte));
+                    assert_eq!(count(&a, byte), end, "len={len} end={end}");
+                }
+            }
+        }
+    }
+
+    #[cfg(all(target_arch = "x86_64", not(feature = "safe-encode")))]
+    #[test]
+    fn test_count_run_simd_parity() {
+        check_count_run(count_run_simd);
+    }
+
+    #[cfg(all(target_arch = "x86_64", not(feature = "safe-encode")))]
+    #[test]
+    fn test_count_run_x86_backends() {
+        check_count_run(count_run_sse2);
+        if has_x86_feature!("avx2") {
+            check_count_run(|a, byte| unsafe { count_run_avx2(a, byte) });
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_count_same_bytes_x86_backends() {
+        check_count_same_bytes(count_same_bytes_sse2);
+        if has_x86_feature!("avx2") {
+            check_count_same_bytes(|a, b| unsafe { count_same_bytes_avx2(a, b) });
+        }
+    }
+
+    #[cfg(not(feature = "safe-decode"))]
+    #[test]
+    fn test_duplicate_overlapping_simd() {
+        for offset in 1usize..16 {
+            for len in 0..70usize {
+                let mut expected: Vec<u8> = (0..offset).map(|i| i as u8 + 1).collect();
+                for _ in 0..len {
+                    expected.push(expected[expected.len() - offset]);
+                }
+
+                // Trailing guard bytes must stay untouched.
+                let mut output = vec![0xAAu8; offset + len + 16];
+                output[..offset].copy_from_slice(&expected[..offset]);
+                let done = unsafe {
+                    duplicate_overlapping_simd(output.as_mut_ptr().add(offset), offset, len)
+                };
+                if !done {
+                    return;
+                }
+                assert_eq!(
+                    &output[..offset + len],
+                    &expected[..],
+                    "offset={offset} len={len}"
+                );
+                assert!(output[offset + len..].iter().all(|&b| b == 0xAA));
+            }
+        }
+    }
+
+    #[cfg(all(
+        target_arch = "wasm32",
+        target_feature = "simd128",
+        feature = "safe-decode"
+    ))]
+    #[test]
+    fn test_duplicate_overlapping_safe() {
+        for offset in 1usize..=40 {
+            for len in [0usize, 1, 15, 16, 17, 31, 48, 100] {
+                let seed: Vec<u8> = (0..offset).map(|i| i as u8 + 1).collect();
+                let mut expected = seed.clone();
+                for _ in 0..len {
+                    expected.push(expected[expected.len() - offset]);
+                }
+
+                let mut output = vec![0u8; offset + len + 7];
+                output[..offset].copy_from_slice(&seed);
+                duplicate_overlapping_safe(&mut output, offset, offset, len);
+                assert_eq!(
+                    &output[..offset + len],
+                    &expected[..],
+                    "offset={offset} len={len}"
+                );
+                assert!(output[offset + len..].iter().all(|&b| b == 0));
+            }
+        }
+    }
 }
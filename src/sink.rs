@@ -1,6 +1,16 @@
 // Source provenance: derived from https://github.com/pseitz/lz4_flex (MIT), commit 975bfa7ac9583da879b5d7578b423232d84f69fe.
 #[allow(unused_imports)]
 use alloc::vec::Vec;
+#[cfg(all(
+    not(all(feature = "safe-encode", feature = "safe-decode")),
+    any(
+        feature = "block",
+        feature = "wasm-exports",
+        test,
+        not(feature = "safe-decode")
+    )
+))]
+use core::mem::MaybeUninit;
 
 use crate::fastcpy::slice_copy;
 
@@ -23,25 +33,6 @@ pub fn vec_sink_for_compression(
     }
 }
 
-/// Returns a Sink implementation appropriate for outputting up to `required_capacity`
-/// bytes at `vec[offset..offset+required_capacity]`.
-/// It can be either a `SliceSink` (pre-filling the vec with zeroes if necessary)
-/// when the `safe-decode` feature is enabled, or `VecSink` otherwise.
-/// The argument `pos` defines the initial output position in the Sink.
-#[cfg(feature = "frame")]
-#[inline]
-pub fn vec_sink_for_decompression(
-    vec: &mut Vec<u8>,
-    offset: usize,
-    pos: usize,
-    required_capacity: usize,
-) -> SliceSink<'_> {
-    {
-        vec.resize(offset + required_capacity, 0);
-        SliceSink::new(&mut vec[offset..], pos)
-    }
-}
-
 pub trait Sink {
     /// Returns a raw ptr to the first unfilled byte of the Sink. Analogous to `[pos..].as_ptr()`.
     #[cfg(not(all(feature = "safe-encode", feature = "safe-decode")))]
@@ -62,6 +53,10 @@ pub trait Sink {
 
     fn capacity(&self) -> usize;
 
+    /// Returns the whole buffer, also the bytes after `pos`.
+    #[cfg(all(feature = "safe-decode", any(feature = "block", test)))]
+    fn as_slice(&self) -> &[u8];
+
     #[cfg(not(all(feature = "safe-encode", feature = "safe-decode")))]
     unsafe fn set_pos(&mut self, new_pos: usize);
 
@@ -149,6 +144,12 @@ impl Sink for SliceSink<'_> {
         self.output.len()
     }
 
+    #[inline]
+    #[cfg(all(feature = "safe-decode", any(feature = "block", test)))]
+    fn as_slice(&self) -> &[u8] {
+        self.output
+    }
+
     #[cfg(not(all(feature = "safe-encode", feature = "safe-decode")))]
     #[inline]
     unsafe fn set_pos(&mut self, new_pos: usize) {
@@ -191,6 +192,11 @@ impl Sink for SliceSink<'_> {
     #[cfg_attr(feature = "nightly", optimize(size))] // to avoid loop unrolling
     fn extend_from_within_overlapping(&mut self, start: usize, num_bytes: usize) {
         let offset = self.pos - start;
+        // The SIMD kernel is slice based and bounds checked, so it keeps the safe-decode
+        // guarantees.
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        crate::simd::duplicate_overlapping_safe(self.output, self.pos, offset, num_bytes);
+        #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
         for i in start + offset..start + offset + num_bytes {
             self.output[i] = self.output[i - offset];
         }
@@ -215,17 +221,24 @@ pub struct PtrSink {
 
 #[cfg(not(all(feature = "safe-encode", feature = "safe-decode")))]
 impl PtrSink {
-    /// Creates a `Sink` backed by the given byte slice.
-    /// `pos` defines the initial output position in the Sink.
+    /// Creates a `Sink` backed by the given, possibly uninitialized, slice.
+    /// `pos` defines the initial output position in the Sink; `output[..pos]` must be
+    /// initialized.
     /// # Panics
     /// Panics if `pos` is out of bounds.
+    #[cfg(any(
+        feature = "block",
+        feature = "wasm-exports",
+        test,
+        not(feature = "safe-decode")
+    ))]
     #[inline]
-    pub fn from_vec(output: &mut Vec<u8>, pos: usize) -> Self {
-        // SAFETY: Bytes behind pointer may be uninitialized.
+    pub fn from_uninit(output: &mut [MaybeUninit<u8>], pos: usize) -> Self {
+        let _ = &mut output[..pos]; // bounds check pos
         Self {
-            output: output.as_mut_ptr(),
+            output: output.as_mut_ptr() as *mut u8,
             pos,
-            cap: output.capacity(),
+            cap: output.len(),
         }
     }
 }
@@ -270,6 +283,12 @@ impl Sink for PtrSink {
         self.cap
     }
 
+    #[inline]
+    #[cfg(all(feature = "safe-decode", any(feature = "block", test)))]
+    fn as_slice(&self) -> &[u8] {
+        unreachable!();
+    }
+
     #[cfg(not(all(feature = "safe-encode", feature = "safe-decode")))]
     #[inline]
     unsafe fn set_pos(&mut self, new_pos: usize) {
@@ -1,5 +1,5 @@
-use alloc::{string::String, vec};
 use alloc::vec::Vec;
+use alloc::{string::String, vec};
 use core::hash::Hasher;
 
 use crate::hash::XxHash32;
@@ -30,7 +30,12 @@ const PROFILE_COUNTER_DUP_OVERLAP_FALLBACK_BYTE: u32 = 5;
 const PROFILE_COUNTER_COPY_FROM_DICT_CALLS: u32 = 6;
 const PROFILE_COUNTER_LITERAL_BYTES: u32 = 7;
 const PROFILE_COUNTER_MATCH_BYTES: u32 = 8;
+const PROFILE_COUNTER_DUP_OVERLAP_SMALL_SIMD: u32 = 9;
 const PROFILE_COUNTER_CHECKSUM: u32 = 100;
+#[cfg(feature = "compress-prof")]
+const COMPRESS_PROFILE_COUNTER_RUN_MATCHES: u32 = 0;
+#[cfg(feature = "compress-prof")]
+const COMPRESS_PROFILE_COUNTER_RUN_BYTES: u32 = 1;
 const FIXTURE_TEXT_50KB: u32 = 0;
 const FIXTURE_JSON_50KB: u32 = 1;
 const TEXT_50KB_BYTES: &[u8] = include_bytes!("../bench-data/text_50kb.txt");
@@ -189,9 +194,56 @@ pub extern "C" fn wasm_hash_consistency() -> i32 {
     }
 }
 
+/// Returns 1 if this module was built with `simd128`, 0 otherwise. The JS loader
+/// (`js/loader.mjs`) uses it to check that it picked the intended artifact.
+#[no_mangle]
+pub extern "C" fn wasm_simd_enabled() -> i32 {
+    cfg!(target_feature = "simd128") as i32
+}
+
+/// Allocates `len` zeroed bytes in linear memory, for passing buffers to the pointer-based
+/// exports. Release them with `wasm_dealloc(ptr, len)`.
+#[no_mangle]
+pub extern "C" fn wasm_alloc(len: u32) -> *mut u8 {
+    alloc::boxed::Box::into_raw(vec![0u8; len as usize].into_boxed_slice()) as *mut u8
+}
+
+/// # Safety
+/// `ptr` and `len` must come from a single `wasm_alloc` call that was not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn wasm_dealloc(ptr: *mut u8, len: u32) {
+    drop(alloc::boxed::Box::from_raw(
+        core::ptr::slice_from_raw_parts_mut(ptr, len as usize),
+    ));
+}
+
+/// `block::compress_dest_size` on `src_len` bytes at `src` into `dst_capacity` bytes at `dst`.
+/// Returns the number of input bytes consumed in the high 32 bits and the number of bytes
+/// written in the low 32 bits.
+///
+/// # Safety
+/// Both ranges must lie in buffers from `wasm_alloc` and must not overlap.
+#[no_mangle]
+pub unsafe extern "C" fn wasm_compress_dest_size(
+    src: *const u8,
+    src_len: u32,
+    dst: *mut u8,
+    dst_capacity: u32,
+) -> u64 {
+    let input = core::slice::from_raw_parts(src, src_len as usize);
+    let output = core::slice::from_raw_parts_mut(dst, dst_capacity as usize);
+    let (consumed, written) = crate::block::compress_dest_size(input, output);
+    ((consumed as u64) << 32) | written as u64
+}
+
 #[no_mangle]
 pub extern "C" fn wasm_compress_repeated(iters: u32, size: u32) -> u64 {
-    let input = payload_repetitive_json(size as usize);
+    wasm_compress_repeated_case(iters, size, CASE_REPETITIVE_JSON)
+}
+
+#[no_mangle]
+pub extern "C" fn wasm_compress_repeated_case(iters: u32, size: u32, case_id: u32) -> u64 {
+    let input = payload_for_case(size as usize, case_id);
     let mut acc = 0u64;
 
     for i in 0..iters {
@@ -318,7 +370,7 @@ fn decode_mix_for_payload(size: u32, case_id: u32) -> DecodeMix {
 }
 
 fn profile_counter_value(counter_id: u32) -> u64 {
-    let s = crate::block::decompress::read_decompress_profile();
+    let s = crate::block::profile::read_decompress_profile();
     match counter_id {
         PROFILE_COUNTER_FAST_TOKEN_HITS => s.fast_token_hits,
         PROFILE_COUNTER_DUP_NONOVERLAP_WILD => s.duplicate_nonoverlap_wild,
@@ -329,6 +381,17 @@ fn profile_counter_value(counter_id: u32) -> u64 {
         PROFILE_COUNTER_COPY_FROM_DICT_CALLS => s.copy_from_dict_calls,
         PROFILE_COUNTER_LITERAL_BYTES => s.literal_bytes,
         PROFILE_COUNTER_MATCH_BYTES => s.match_bytes,
+        PROFILE_COUNTER_DUP_OVERLAP_SMALL_SIMD => s.duplicate_overlap_small_simd,
+        _ => 0,
+    }
+}
+
+#[cfg(feature = "compress-prof")]
+fn compress_profile_counter_value(counter_id: u32) -> u64 {
+    let s = crate::block::compress::read_compress_profile();
+    match counter_id {
+        COMPRESS_PROFILE_COUNTER_RUN_MATCHES => s.run_matches,
+        COMPRESS_PROFILE_COUNTER_RUN_BYTES => s.run_bytes,
         _ => 0,
     }
 }
@@ -387,7 +450,7 @@ pub extern "C" fn wasm_decompress_repeated_fixture(iters: u32, fixture_id: u32)
 
 #[no_mangle]
 pub extern "C" fn wasm_decompress_profile_reset() {
-    crate::block::decompress::reset_decompress_profile();
+    crate::block::profile::reset_decompress_profile();
 }
 
 #[no_mangle]
@@ -407,7 +470,7 @@ pub extern "C" fn wasm_decompress_profile_run_case_counter(
     case_id: u32,
     counter_id: u32,
 ) -> u64 {
-    crate::block::decompress::reset_decompress_profile();
+    crate::block::profile::reset_decompress_profile();
     let checksum = wasm_decompress_repeated_case(iters, size, case_id);
     if counter_id == PROFILE_COUNTER_CHECKSUM {
         return checksum;
@@ -415,6 +478,34 @@ pub extern "C" fn wasm_decompress_profile_run_case_counter(
     profile_counter_value(counter_id)
 }
 
+#[cfg(feature = "compress-prof")]
+#[no_mangle]
+pub extern "C" fn wasm_compress_profile_reset() {
+    crate::block::compress::reset_compress_profile();
+}
+
+#[cfg(feature = "compress-prof")]
+#[no_mangle]
+pub extern "C" fn wasm_compress_profile_counter(counter_id: u32) -> u64 {
+    compress_profile_counter_value(counter_id)
+}
+
+#[cfg(feature = "compress-prof")]
+#[no_mangle]
+pub extern "C" fn wasm_compress_profile_run_case_counter(
+    iters: u32,
+    size: u32,
+    case_id: u32,
+    counter_id: u32,
+) -> u64 {
+    crate::block::compress::reset_compress_profile();
+    let checksum = wasm_compress_repeated_case(iters, size, case_id);
+    if counter_id == PROFILE_COUNTER_CHECKSUM {
+        return checksum;
+    }
+    compress_profile_counter_value(counter_id)
+}
+
 #[no_mangle]
 pub extern "C" fn wasm_decompress_mix_literal_bytes(size: u32) -> u64 {
     decode_mix_for_payload(size, CASE_REPETITIVE_JSON).literal_bytes
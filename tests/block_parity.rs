@@ -1,8 +1,16 @@
 #![cfg(feature = "block")]
 
 use lz4_flex_wasm_simd::block::{
-    compress, compress_prepend_size, decompress, decompress_size_prepended,
+    compress, compress_append, compress_dest_size, compress_into_uninit, compress_into_with_stats,
+    compress_prepend_size, compress_with_dict, compress_with_dict_and_stats, compress_with_stats,
+    decompress, decompress_append, decompress_in_place, decompress_in_place_margin,
+    decompress_into_uninit, decompress_into_with_prefix, decompress_partial_into,
+    decompress_size_prepended, decompressed_len, get_maximum_output_size, validate, CompressStats,
+    Compressor, DecompressError, DEFAULT_MEMORY_USAGE,
 };
+use proptest::prelude::*;
+
+mod common;
 
 #[test]
 fn block_roundtrip_variants() {
@@ -28,3 +36,652 @@ fn block_matches_lz4_flex_output_roundtrip() {
         .expect("lz4_flex decompress local output");
     assert_eq!(restored_upstream, data);
 }
+
+#[test]
+fn partial_decompression_matches_prefix() {
+    let data = include_bytes!("../bench-data/json_50kb.json");
+    let compressed = compress(data);
+    let mut out = vec![0u8; data.len()];
+    for target in (0..data.len())
+        .step_by(997)
+        .chain([data.len() - 1, data.len()])
+    {
+        let wrote = decompress_partial_into(&compressed, &mut out, target).expect("partial");
+        assert_eq!(wrote, target);
+        assert_eq!(&out[..target], &data[..target], "target={target}");
+    }
+}
+
+#[test]
+fn uninit_buffers_roundtrip() {
+    let data = include_bytes!("../bench-data/text_50kb.txt");
+    let mut compressed = Vec::with_capacity(get_maximum_output_size(data.len()));
+    let compressed = compress_into_uninit(data, compressed.spare_capacity_mut()).expect("compress");
+    assert_eq!(compressed, &compress(data)[..]);
+
+    let mut out = Vec::with_capacity(data.len());
+    let restored =
+        decompress_into_uninit(compressed, out.spare_capacity_mut()).expect("decompress");
+    assert_eq!(restored, &data[..]);
+
+    let mut short = Vec::with_capacity(data.len());
+    let short = &mut short.spare_capacity_mut()[..data.len() - 1];
+    assert!(decompress_into_uninit(compressed, short).is_err());
+}
+
+#[test]
+fn append_records_into_one_buffer() {
+    let records: Vec<Vec<u8>> = (0..20u8)
+        .map(|i| {
+            let line = format!(
+                "record {i} payload payload payload {}",
+                "x".repeat(i as usize * 7)
+            );
+            line.into_bytes()
+        })
+        .collect();
+
+    let mut compressed = b"header".to_vec();
+    let mut ranges = Vec::new();
+    for record in &records {
+        let start = compressed.len();
+        let len = compress_append(record, &mut compressed);
+        assert_eq!(compressed.len(), start + len);
+        assert_eq!(&compressed[start..], &compress(record)[..]);
+        ranges.push(start..start + len);
+    }
+    assert_eq!(&compressed[..6], b"header");
+
+    let mut arena = Vec::new();
+    for (record, range) in records.iter().zip(&ranges) {
+        let start = arena.len();
+        let len = decompress_append(&compressed[range.clone()], &mut arena, record.len())
+            .expect("decompress_append");
+        assert_eq!(&arena[start..start + len], &record[..]);
+    }
+    assert_eq!(arena, records.concat());
+
+    // A failed append leaves the buffer as it was.
+    let last = &records[records.len() - 1];
+    let range = ranges[ranges.len() - 1].clone();
+    assert!(decompress_append(&compressed[range], &mut arena, last.len() - 1).is_err());
+    assert_eq!(arena, records.concat());
+}
+
+/// Checks the end-of-block restrictions of the block format: the last sequence holds at least 5
+/// literals and the last match starts at least 12 bytes before the end.
+fn check_end_of_block_rules(block: &[u8], len: usize) {
+    fn read_length(block: &[u8], pos: &mut usize, nibble: usize) -> usize {
+        let mut length = nibble;
+        if nibble == 15 {
+            loop {
+                let byte = block[*pos];
+                *pos += 1;
+                length += byte as usize;
+                if byte != 255 {
+                    break;
+                }
+            }
+        }
+        length
+    }
+
+    let (mut pos, mut out) = (0, 0);
+    let mut last_match_start = None;
+    loop {
+        let token = block[pos] as usize;
+        pos += 1;
+        let literals = read_length(block, &mut pos, token >> 4);
+        pos += literals;
+        out += literals;
+        if pos == block.len() {
+            if let Some(start) = last_match_start {
+                assert!(literals >= 5, "{literals} last literals");
+                assert!(
+                    len - start >= 12,
+                    "last match starts {} bytes before the end",
+                    len - start
+                );
+            }
+            assert_eq!(out, len);
+            return;
+        }
+        pos += 2;
+        last_match_start = Some(out);
+        out += read_length(block, &mut pos, token & 15) + 4;
+    }
+}
+
+#[test]
+fn dest_size_packs_pages() {
+    const PAGE: usize = 4096;
+    let text = include_bytes!("../bench-data/text_50kb.txt");
+    let mut data = text.to_vec();
+    data.extend_from_slice(&[b'z'; 20_000]);
+    data.extend_from_slice(&text[..5000]);
+
+    let mut pages = Vec::new();
+    let mut rest = &data[..];
+    while !rest.is_empty() {
+        let mut page = vec![0u8; PAGE];
+        let (consumed, written) = compress_dest_size(rest, &mut page);
+        assert!(consumed > 0 && written <= PAGE);
+        if consumed < rest.len() {
+            // Only the length bytes of the last literals can leave a gap.
+            assert!(written >= PAGE - 2);
+        }
+        page.truncate(written);
+        assert_eq!(decompress(&page, consumed).unwrap(), &rest[..consumed]);
+        check_end_of_block_rules(&page, consumed);
+        pages.push(page);
+        rest = &rest[consumed..];
+    }
+    assert!(pages.len() < data.len() / PAGE);
+
+    // Whatever fits whole is compressed exactly like `compress`.
+    let mut out = vec![0u8; get_maximum_output_size(text.len())];
+    let (consumed, written) = compress_dest_size(text, &mut out);
+    assert_eq!(consumed, text.len());
+    assert_eq!(&out[..written], &compress(text)[..]);
+
+    assert_eq!(compress_dest_size(text, &mut []), (0, 0));
+    // A single byte holds an empty block.
+    let mut one = [0xAAu8];
+    assert_eq!(compress_dest_size(text, &mut one), (0, 1));
+    assert_eq!(one, [0]);
+}
+
+#[test]
+fn prefix_bounds_the_match_window() {
+    use lz4_flex_wasm_simd::block::DecompressError;
+
+    // "a", then a 4 byte match at offset 3, then "z".
+    let block = [0x10, b'a', 3, 0, 0x10, b'z'];
+    let mut output = *b"xy______";
+    assert_eq!(
+        decompress_into_with_prefix(&block, &mut output, 2).unwrap(),
+        6
+    );
+    assert_eq!(&output, b"xyaxyaxz");
+
+    let mut output = *b"y______";
+    assert!(matches!(
+        decompress_into_with_prefix(&block, &mut output, 1),
+        Err(DecompressError::OffsetOutOfBounds {
+            input_pos: 0,
+            output_pos: 2,
+            offset: 3
+        })
+    ));
+    let mut output = *b"xy_____";
+    assert!(matches!(
+        decompress_into_with_prefix(&block, &mut output, 2),
+        Err(DecompressError::OutputTooSmall { .. })
+    ));
+}
+
+#[test]
+fn memory_usage_trades_table_size_for_ratio() {
+    let json = include_bytes!("../bench-data/json_50kb.json");
+    let mut data = json.to_vec();
+    data.extend_from_slice(include_bytes!("../bench-data/text_50kb.txt"));
+    assert_eq!(
+        Compressor::<DEFAULT_MEMORY_USAGE>::compress(&data),
+        compress(&data)
+    );
+    assert_eq!(
+        Compressor::<DEFAULT_MEMORY_USAGE>::compress(json),
+        compress(json)
+    );
+
+    let small = Compressor::<10>::compress(&data);
+    let large = Compressor::<18>::compress(&data);
+    assert!(large.len() < compress(&data).len());
+    assert!(compress(&data).len() < small.len());
+    for block in [small, large] {
+        assert_eq!(decompress(&block, data.len()).unwrap(), data);
+    }
+}
+
+#[test]
+fn small_inputs_around_stack_table_limit() {
+    let json = include_bytes!("../bench-data/json_50kb.json");
+    for len in [0, 1, 13, 64, 255, 256, 257, 1000, 1023, 1024, 1025, 1100] {
+        let data = &json[3000..3000 + len];
+        for block in [compress(data), Compressor::<10>::compress(data)] {
+            assert_eq!(lz4_flex::block::decompress(&block, len).unwrap(), data);
+        }
+        // The dictionary counts towards the limit.
+        let dict = &json[..1024 - len.min(1024) / 2];
+        let block = compress_with_dict(data, dict);
+        assert_eq!(
+            lz4_flex::block::decompress_with_dict(&block, len, dict).unwrap(),
+            data
+        );
+    }
+}
+
+#[test]
+fn runs_after_matches() {
+    let text = include_bytes!("../bench-data/text_50kb.txt");
+    let mut data = Vec::new();
+    for (i, run) in [0, 1, 31, 32, 33, 300, 70_000].into_iter().enumerate() {
+        // The second copy of the text matches the first up to the end of the shorter run, the
+        // rest of the run follows that match.
+        let byte = [0u8, b' ', 0xFF][i % 3];
+        data.extend_from_slice(&text[..200]);
+        data.extend_from_slice(&[byte; 8]);
+        data.push(b'x');
+        data.extend_from_slice(&text[..200]);
+        data.extend(core::iter::repeat(byte).take(run));
+    }
+    // A run at the end of the input, inside the last literals.
+    data.extend_from_slice(&text[..200]);
+    data.extend_from_slice(&[0; 40]);
+
+    for end in [data.len(), data.len() - 3, data.len() - 20, 250, 600] {
+        let data = &data[..end];
+        for block in [compress(data), Compressor::<10>::compress(data)] {
+            assert_eq!(
+                lz4_flex::block::decompress(&block, data.len()).unwrap(),
+                data
+            );
+            check_end_of_block_rules(&block, data.len());
+        }
+        let dict = &text[..4096];
+        let block = compress_with_dict(data, dict);
+        assert_eq!(
+            lz4_flex::block::decompress_with_dict(&block, data.len(), dict).unwrap(),
+            data
+        );
+        for capacity in [64, 300, 1000] {
+            let mut page = vec![0u8; capacity];
+            let (consumed, written) = compress_dest_size(data, &mut page);
+            assert_eq!(
+                decompress(&page[..written], consumed).unwrap(),
+                &data[..consumed]
+            );
+            check_end_of_block_rules(&page[..written], consumed);
+        }
+    }
+
+    // Runs cost few bytes, whatever their length.
+    let zeros = vec![0u8; 1 << 20];
+    assert!(compress(&zeros).len() < 4200);
+    assert_eq!(decompress(&compress(&zeros), zeros.len()).unwrap(), zeros);
+}
+
+#[test]
+fn stats_describe_the_blocks() {
+    let json = include_bytes!("../bench-data/json_50kb.json");
+    let text = include_bytes!("../bench-data/text_50kb.txt");
+    let mut noise = vec![0u8; 20_000];
+    let mut seed = 0x9e37_79b9u32;
+    for b in &mut noise {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        *b = seed as u8;
+    }
+    let mut big = text.to_vec();
+    big.extend_from_slice(&noise.repeat(4));
+    big.extend_from_slice(text);
+    big.extend_from_slice(&[0; 5000]);
+
+    for data in [&json[..], &text[..200], &noise, &big, b"short"] {
+        let (block, stats) = compress_with_stats(data);
+        assert_eq!(block, compress(data));
+        assert_eq!(stats.blocks, 1);
+        assert_eq!(stats.input_bytes, data.len() as u64);
+        assert_eq!(stats.output_bytes, block.len() as u64);
+        assert_eq!(stats.literal_bytes + stats.match_bytes, data.len() as u64);
+        assert_eq!(stats.match_lengths.iter().sum::<u64>(), stats.matches);
+        assert_eq!(stats.offsets.iter().sum::<u64>(), stats.matches);
+        assert_eq!(stats.match_lengths[..2], [0, 0]);
+        // The run fast path adds matches without a lookup.
+        assert!(stats.hash_hits <= stats.matches);
+        // One sequence per match, plus the last literals.
+        let mut sequences = 0;
+        let mut pos = 0;
+        while pos < block.len() {
+            let token = block[pos] as usize;
+            pos += 1;
+            let mut literals = token >> 4;
+            if literals == 15 {
+                while block[pos] == 255 {
+                    literals += 255;
+                    pos += 1;
+                }
+                literals += block[pos] as usize;
+                pos += 1;
+            }
+            pos += literals;
+            if pos < block.len() {
+                pos += 2;
+                if token & 15 == 15 {
+                    while block[pos] == 255 {
+                        pos += 1;
+                    }
+                    pos += 1;
+                }
+            }
+            sequences += 1;
+        }
+        assert_eq!(stats.matches, sequences - 1);
+    }
+
+    let (_, noise_stats) = compress_with_stats(&noise);
+    assert!(noise_stats.step_escalations > 0);
+    assert!(noise_stats.literal_bytes > noise.len() as u64 * 9 / 10);
+    let (_, json_stats) = compress_with_stats(json);
+    assert!(json_stats.hash_hits > 0);
+    assert!(json_stats.match_bytes > json_stats.literal_bytes);
+    // Beyond 64KB the search misses candidates that are out of the window.
+    let (_, big_stats) = compress_with_stats(&big);
+    assert!(big_stats.hash_misses > 0);
+
+    // A dictionary turns literals of the first records into matches.
+    let (block, dict_stats) = compress_with_dict_and_stats(&json[..2000], &json[2000..]);
+    assert_eq!(block, compress_with_dict(&json[..2000], &json[2000..]));
+    let (_, no_dict_stats) = compress_with_stats(&json[..2000]);
+    assert!(dict_stats.literal_bytes < no_dict_stats.literal_bytes);
+
+    // Statistics add up over calls.
+    let mut total = CompressStats::new();
+    let mut output = vec![0u8; get_maximum_output_size(json.len())];
+    for chunk in json.chunks(4096) {
+        let written = compress_into_with_stats(chunk, &mut output, &mut total).unwrap();
+        assert_eq!(&output[..written], &compress(chunk)[..]);
+    }
+    let mut sum = CompressStats::new();
+    for chunk in json.chunks(4096) {
+        sum += &compress_with_stats(chunk).1;
+    }
+    assert_eq!(total, sum);
+    assert_eq!(total.blocks, json.chunks(4096).len() as u64);
+    assert_eq!(
+        Compressor::<10>::compress_with_stats(json).0,
+        Compressor::<10>::compress(json)
+    );
+}
+
+#[test]
+fn partial_stops_at_target() {
+    let input = b"partial decoding stops at the target, partial decoding stops at the target";
+    let compressed = compress(input);
+    for target in 0..=input.len() + 8 {
+        let mut out = [0xAAu8; 96];
+        let wrote = decompress_partial_into(&compressed, &mut out, target).unwrap();
+        assert_eq!(wrote, target.min(input.len()), "target={target}");
+        assert_eq!(&out[..wrote], &input[..wrote]);
+        assert!(out[target..].iter().all(|&b| b == 0xAA), "target={target}");
+    }
+
+    // The block is cut right after its literals; the match is never read.
+    let truncated = [0x84, b'a', b'b', b'c', b'd', b'e', b'f', b'g', b'h'];
+    let mut out = [0u8; 16];
+    assert_eq!(decompress_partial_into(&truncated, &mut out, 8).unwrap(), 8);
+    assert_eq!(&out[..8], b"abcdefgh");
+    // Past the literals, the cut looks like the end of the block.
+    assert_eq!(decompress_partial_into(&truncated, &mut out, 9).unwrap(), 8);
+}
+
+#[test]
+fn validate_agrees_with_decoder_on_corrupted_blocks() {
+    let data = include_bytes!("../bench-data/json_50kb.json");
+    let data = &data[..4096];
+    let compressed = compress(data);
+    assert_eq!(decompressed_len(&compressed).unwrap(), data.len());
+    validate(&compressed, data.len(), b"").unwrap();
+
+    let mut seed = 0x2545_f491u32;
+    for _ in 0..2000 {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        let mut corrupted = compressed.clone();
+        let pos = seed as usize % corrupted.len();
+        corrupted[pos] ^= 1 << (seed >> 29);
+
+        if validate(&corrupted, data.len(), b"").is_ok() {
+            let restored = decompress(&corrupted, data.len()).expect("validated block");
+            assert_eq!(restored.len(), data.len());
+            assert_eq!(decompressed_len(&corrupted).unwrap(), data.len());
+        }
+    }
+}
+
+#[test]
+fn errors_report_sequence_positions() {
+    use lz4_flex_wasm_simd::block::{decompress_into, DecompressError};
+
+    let mut out = [0u8; 32];
+    // "a", then a 4 byte match at offset 1, then a sequence at input 4 whose 3 literals are cut.
+    let block = [0x10, b'a', 1, 0, 0x30, b'b'];
+    assert!(matches!(
+        decompress_into(&block, &mut out),
+        Err(DecompressError::LiteralOutOfBounds {
+            input_pos: 4,
+            output_pos: 5,
+            literal_length: 3,
+        })
+    ));
+    let block = [0x10, b'a', 1, 0, 0x10, b'b', 9, 0, 0x00];
+    assert!(matches!(
+        decompress_into(&block, &mut out),
+        Err(DecompressError::OffsetOutOfBounds {
+            input_pos: 4,
+            output_pos: 6,
+            offset: 9,
+        })
+    ));
+    let block = [0x10, b'a', 1, 0, 0x0F, 1, 0];
+    assert!(matches!(
+        decompress_into(&block, &mut out),
+        Err(DecompressError::ExpectedAnotherByte {
+            input_pos: 4,
+            output_pos: 5,
+        })
+    ));
+    assert!(matches!(
+        validate(&[0x10, b'a', 1, 0, 0x10, b'b', 9, 0, 0x00], 15, b""),
+        Err(DecompressError::OffsetOutOfBounds {
+            input_pos: 4,
+            output_pos: 6,
+            offset: 9,
+        })
+    ));
+}
+
+#[test]
+fn offset_zero_is_rejected() {
+    use lz4_flex_wasm_simd::block::{decompress_into, DecompressError};
+
+    // Slow path: a short block.
+    assert!(matches!(
+        decompress(&[0x00, 0, 0, 0x00], 32),
+        Err(DecompressError::OffsetOutOfBounds { offset: 0, .. })
+    ));
+    // Fast path: an 8 byte match after one literal, with enough input left over.
+    let mut block = vec![0x14, b'a', 0, 0, 0xF0, 5];
+    block.extend_from_slice(&[b'b'; 20]);
+    let mut out = [0u8; 100];
+    assert!(matches!(
+        decompress_into(&block, &mut out),
+        Err(DecompressError::OffsetOutOfBounds {
+            input_pos: 0,
+            output_pos: 1,
+            offset: 0,
+        })
+    ));
+    // Before any output, the default decoder's fast path clamps offsets to the window, which
+    // must not produce an offset of 0 either.
+    if cfg!(not(feature = "safe-decode")) {
+        block[2] = 3;
+        block[0] = 0x04;
+        block.remove(1);
+        assert!(decompress_into(&block, &mut out).is_err());
+    }
+}
+
+proptest! {
+    #[test]
+    fn cross_decodes_with_lz4_flex(data in common::data_shapes(12, 3000)) {
+        let ours = compress(&data);
+        prop_assert!(ours.len() <= get_maximum_output_size(data.len()));
+        prop_assert_eq!(lz4_flex::block::decompress(&ours, data.len()).unwrap(), &data[..]);
+        prop_assert_eq!(decompress(&ours, data.len()).unwrap(), &data[..]);
+        prop_assert_eq!(decompressed_len(&ours).unwrap(), data.len());
+
+        let theirs = lz4_flex::block::compress(&data);
+        prop_assert_eq!(decompress(&theirs, data.len()).unwrap(), &data[..]);
+        prop_assert!(validate(&theirs, data.len(), b"").is_ok());
+
+        let prepended = compress_prepend_size(&data);
+        prop_assert_eq!(
+            lz4_flex::block::decompress_size_prepended(&prepended).unwrap(),
+            &data[..]
+        );
+        prop_assert_eq!(
+            decompress_size_prepended(&lz4_flex::block::compress_prepend_size(&data)).unwrap(),
+            &data[..]
+        );
+    }
+
+    #[test]
+    fn dest_size_blocks_decode_to_consumed_prefix(
+        data in common::data_shapes(12, 3000),
+        capacity in 0usize..4000,
+    ) {
+        let mut out = vec![0u8; capacity];
+        let (consumed, written) = compress_dest_size(&data, &mut out);
+        prop_assert!(consumed <= data.len());
+        prop_assert!(written <= capacity);
+        if capacity > 0 && consumed < data.len() {
+            prop_assert!(written + 2 >= capacity);
+        }
+        if written > 0 {
+            let block = &out[..written];
+            prop_assert!(validate(block, consumed, b"").is_ok());
+            prop_assert_eq!(
+                lz4_flex::block::decompress(block, consumed).unwrap(),
+                &data[..consumed]
+            );
+            check_end_of_block_rules(block, consumed);
+        }
+    }
+
+    #[test]
+    fn in_place_with_margin(data in common::data_shapes(12, 3000)) {
+        for compressed in [compress(&data), lz4_flex::block::compress(&data)] {
+            let margin = decompress_in_place_margin(compressed.len());
+            let mut buf = vec![0u8; data.len() + margin];
+            let (start, end) = (buf.len() - compressed.len(), buf.len());
+            buf[start..].copy_from_slice(&compressed);
+            let len = decompress_in_place(&mut buf, start..end).unwrap();
+            prop_assert_eq!(&buf[..len], &data[..]);
+        }
+    }
+
+    #[test]
+    fn in_place_short_margin_fails_cleanly(
+        data in common::data_shapes(12, 3000),
+        short in 0usize..48,
+    ) {
+        let compressed = compress(&data);
+        let room = decompress_in_place_margin(compressed.len()).saturating_sub(short);
+        let mut buf = vec![0u8; (data.len() + room).max(compressed.len())];
+        let (start, end) = (buf.len() - compressed.len(), buf.len());
+        buf[start..].copy_from_slice(&compressed);
+        match decompress_in_place(&mut buf, start..end) {
+            Ok(len) => prop_assert_eq!(&buf[..len], &data[..]),
+            Err(err) => prop_assert!(matches!(err, DecompressError::InPlaceOverlap { .. }), "{}", err),
+        }
+    }
+
+    #[test]
+    fn in_place_agrees_with_decompress(
+        input in proptest::collection::vec(any::<u8>(), 1..300),
+        front in 0usize..600,
+    ) {
+        let mut buf = vec![0u8; front + input.len()];
+        buf[front..].copy_from_slice(&input);
+        let end = buf.len();
+        if let Ok(len) = decompress_in_place(&mut buf, front..end) {
+            // The decoders clamp offsets past the output on their fast path, which depends on the
+            // room left, so only valid blocks have to decode the same way.
+            if validate(&input, len, b"").is_ok() {
+                prop_assert_eq!(decompress(&input, len).unwrap(), &buf[..len]);
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_decode_with_prefix(
+        data in common::data_shapes(12, 3000),
+        chunk_len in 1usize..5000,
+    ) {
+        let mut output = vec![0u8; data.len()];
+        let mut pos = 0usize;
+        for chunk in data.chunks(chunk_len) {
+            let prefix = &data[pos.saturating_sub(64 * 1024)..pos];
+            let block = if pos % 2 == 0 {
+                compress_with_dict(chunk, prefix)
+            } else {
+                lz4_flex::block::compress_with_dict(chunk, prefix)
+            };
+            let len = decompress_into_with_prefix(&block, &mut output[..pos + chunk.len()], pos)
+                .unwrap();
+            prop_assert_eq!(len, chunk.len());
+            pos += len;
+        }
+        prop_assert_eq!(output, data);
+    }
+
+    #[test]
+    fn cross_decodes_with_lz4_flex_using_dicts(
+        data in common::data_shapes(8, 2000),
+        dict in common::data_shapes(4, 30_000),
+    ) {
+        use lz4_flex_wasm_simd::block::decompress_with_dict;
+
+        let ours = compress_with_dict(&data, &dict);
+        prop_assert_eq!(
+            lz4_flex::block::decompress_with_dict(&ours, data.len(), &dict).unwrap(),
+            &data[..]
+        );
+        prop_assert_eq!(decompress_with_dict(&ours, data.len(), &dict).unwrap(), &data[..]);
+        prop_assert!(validate(&ours, data.len(), &dict).is_ok());
+
+        let theirs = lz4_flex::block::compress_with_dict(&data, &dict);
+        prop_assert_eq!(decompress_with_dict(&theirs, data.len(), &dict).unwrap(), &data[..]);
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(48))]
+
+    #[test]
+    fn memory_usage_levels_cross_decode(
+        data in common::data_shapes(12, 80_000),
+        dict in proptest::collection::vec(any::<u8>(), 0..256),
+    ) {
+        fn check<const M: u32>(data: &[u8], dict: &[u8]) -> Result<(), TestCaseError> {
+            let block = Compressor::<M>::compress(data);
+            prop_assert_eq!(lz4_flex::block::decompress(&block, data.len()).unwrap(), data);
+            let block = Compressor::<M>::compress_prepend_size_with_dict(data, dict);
+            prop_assert_eq!(
+                lz4_flex::block::decompress_size_prepended_with_dict(&block, dict).unwrap(),
+                data
+            );
+            let mut page = vec![0u8; 512];
+            let (consumed, written) = Compressor::<M>::compress_dest_size(data, &mut page);
+            prop_assert_eq!(decompress(&page[..written], consumed).unwrap(), &data[..consumed]);
+            Ok(())
+        }
+        check::<10>(&data, &dict)?;
+        check::<12>(&data, &dict)?;
+        check::<16>(&data, &dict)?;
+        check::<18>(&data, &dict)?;
+    }
+}
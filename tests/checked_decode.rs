@@ -0,0 +1,88 @@
+//! Malformed input against the default (unsafe) decoder with `checked-decode`.
+//!
+//! Every block the decoder accepts must be one that `block::validate` accepts, and must decode
+//! to the same bytes as the safe `lz4_flex` decoder. Run under Miri or a sanitizer to check for
+//! out-of-bounds accesses as well.
+#![cfg(all(feature = "checked-decode", not(feature = "safe-decode")))]
+
+use std::mem::MaybeUninit;
+
+use lz4_flex_wasm_simd::block::{
+    compress, compress_with_dict, decompress_into, decompress_into_uninit,
+    decompress_into_with_dict, decompress_partial_into, decompressed_len, validate,
+};
+use proptest::prelude::*;
+
+mod common;
+
+fn check_block(input: &[u8], capacity: usize, dict: &[u8]) -> Result<(), TestCaseError> {
+    let mut out = vec![0u8; capacity];
+    let result = if dict.is_empty() {
+        decompress_into(input, &mut out)
+    } else {
+        decompress_into_with_dict(input, &mut out, dict)
+    };
+
+    match result {
+        Ok(len) => {
+            prop_assert!(validate(input, len, dict).is_ok());
+            let mut expected = vec![0u8; capacity];
+            let expected_len =
+                lz4_flex::block::decompress_into_with_dict(input, &mut expected, dict)
+                    .expect("lz4_flex rejects a block the checked decoder accepted");
+            prop_assert_eq!(&out[..len], &expected[..expected_len]);
+        }
+        Err(_) => {
+            // Only blocks that fail validation or do not fit may be rejected.
+            if let Ok(len) = decompressed_len(input) {
+                prop_assert!(len > capacity || validate(input, len, dict).is_err());
+            }
+        }
+    }
+
+    if dict.is_empty() {
+        let mut uninit = vec![MaybeUninit::<u8>::uninit(); capacity];
+        let uninit_result = decompress_into_uninit(input, &mut uninit).map(|out| out.to_vec());
+        prop_assert_eq!(
+            uninit_result.ok(),
+            result.ok().map(|len| out[..len].to_vec())
+        );
+        let target = capacity / 2;
+        let mut partial = vec![0u8; capacity];
+        if let Ok(len) = decompress_partial_into(input, &mut partial, target) {
+            prop_assert!(len <= target);
+        }
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn random_bytes(
+        input in proptest::collection::vec(any::<u8>(), 0..600),
+        capacity in 0usize..2048,
+        dict in proptest::collection::vec(any::<u8>(), 0..64),
+    ) {
+        check_block(&input, capacity, b"")?;
+        check_block(&input, capacity, &dict)?;
+    }
+
+    #[test]
+    fn mutated_blocks(
+        data in common::data_shapes(40, 200),
+        dict in proptest::collection::vec(any::<u8>(), 1..256),
+        flips in proptest::collection::vec((any::<usize>(), any::<u8>()), 1..4),
+        slack in 0usize..64,
+    ) {
+        for (mut block, dict) in [(compress(&data), &[][..]), (compress_with_dict(&data, &dict), &dict[..])] {
+            for &(pos, xor) in &flips {
+                if !block.is_empty() {
+                    let len = block.len();
+                    block[pos % len] ^= xor;
+                }
+            }
+            check_block(&block, data.len() + slack, dict)?;
+            check_block(&block, data.len().saturating_sub(slack), dict)?;
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Input generators shared by the property tests.
+#![allow(dead_code)]
+
+use proptest::prelude::*;
+
+/// One stretch of generated input.
+#[derive(Debug, Clone)]
+enum Segment {
+    /// Incompressible bytes.
+    Random(Vec<u8>),
+    /// A single byte repeated.
+    Run(u8, usize),
+    /// Bytes from a small alphabet.
+    LowEntropy(Vec<u8>),
+    /// A short pattern repeated, for overlapping matches.
+    Period(Vec<u8>, usize),
+    /// A copy of earlier output, for long distance matches.
+    Repeat(usize, usize),
+}
+
+fn segment(max_len: usize) -> impl Strategy<Value = Segment> {
+    prop_oneof![
+        proptest::collection::vec(any::<u8>(), 0..max_len).prop_map(Segment::Random),
+        (any::<u8>(), 0..max_len).prop_map(|(byte, len)| Segment::Run(byte, len)),
+        proptest::collection::vec(0u8..4, 0..max_len).prop_map(Segment::LowEntropy),
+        (proptest::collection::vec(any::<u8>(), 1..32), 0..max_len)
+            .prop_map(|(pattern, len)| Segment::Period(pattern, len)),
+        (any::<usize>(), 0..max_len).prop_map(|(start, len)| Segment::Repeat(start, len)),
+    ]
+}
+
+/// Data made of up to `max_segments` segments of up to `max_segment_len` bytes each, mixing
+/// random bytes, runs, low-entropy bytes, short periods and repeats of earlier data.
+pub fn data_shapes(max_segments: usize, max_segment_len: usize) -> impl Strategy<Value = Vec<u8>> {
+    proptest::collection::vec(segment(max_segment_len), 0..max_segments).prop_map(|segments| {
+        let mut data = Vec::new();
+        for segment in segments {
+            match segment {
+                Segment::Random(bytes) | Segment::LowEntropy(bytes) => data.extend(bytes),
+                Segment::Run(byte, len) => data.extend(std::iter::repeat(byte).take(len)),
+                Segment::Period(pattern, len) => {
+                    data.extend(pattern.iter().copied().cycle().take(len))
+                }
+                Segment::Repeat(start, len) if !data.is_empty() => {
+                    let end = data.len();
+                    let start = start % end;
+                    for i in 0..len {
+                        data.push(data[start + i % (end - start)]);
+                    }
+                }
+                Segment::Repeat(..) => {}
+            }
+        }
+        data
+    })
+}
@@ -0,0 +1,308 @@
+//! Interoperability with the reference C implementation.
+//!
+//! `tests/conformance` holds frames written by the reference `lz4` CLI (see
+//! `scripts/make_conformance_corpus.sh`), with the sha256 of their content in `MANIFEST.sha256`.
+//! In the other direction, frames from `FrameEncoder` are checked field by field against the
+//! LZ4 frame format, and against `lz4 -d` when the CLI is installed.
+#![cfg(feature = "frame")]
+
+use std::fs;
+use std::hash::Hasher;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use lz4_flex_wasm_simd::frame::{
+    BlockMode, BlockSize, Error, FrameDecoder, FrameEncoder, FrameInfo,
+};
+use sha2::{Digest, Sha256};
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance")
+}
+
+fn manifest() -> Vec<(String, String)> {
+    fs::read_to_string(corpus_dir().join("MANIFEST.sha256"))
+        .unwrap()
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| {
+            let (hash, name) = line.split_once("  ").unwrap();
+            (name.to_string(), hash.to_string())
+        })
+        .collect()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Decodes every frame in `input`, stepping over skippable frames. The decoder stops at the end
+/// of each frame, so it is read until the input is used up.
+fn decode_all(input: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut decoder = FrameDecoder::new(input);
+    let mut out = Vec::new();
+    while !decoder.get_ref().is_empty() {
+        match decoder.read_to_end(&mut out) {
+            Ok(_) => {}
+            Err(err) => match Error::from(err) {
+                Error::SkippableFrame(len) => {
+                    io::copy(&mut decoder.get_mut().take(len as u64), &mut io::sink())?;
+                }
+                err => return Err(err),
+            },
+        }
+    }
+    Ok(out)
+}
+
+#[test]
+fn reference_frames_match_manifest() {
+    let manifest = manifest();
+    let mut files: Vec<String> = fs::read_dir(corpus_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".lz4"))
+        .collect();
+    files.sort();
+    let mut listed: Vec<String> = manifest.iter().map(|(name, _)| name.clone()).collect();
+    listed.sort();
+    assert_eq!(files, listed, "every frame is listed in the manifest");
+
+    for (name, hash) in manifest {
+        if name == "dictid.lz4" {
+            continue;
+        }
+        let frame = fs::read(corpus_dir().join(&name)).unwrap();
+        let decoded = decode_all(&frame).unwrap_or_else(|err| panic!("{name}: {err}"));
+        assert_eq!(sha256_hex(&decoded), hash, "{name}");
+    }
+}
+
+#[test]
+fn reference_frames_decode_with_small_reads() {
+    for name in [
+        "text-16k-linked.lz4",
+        "big-b4-linked.lz4",
+        "json-b4-linked-blockcrc-size.lz4",
+    ] {
+        let frame = fs::read(corpus_dir().join(name)).unwrap();
+        let mut decoder = FrameDecoder::new(&frame[..]);
+        let mut out = Vec::new();
+        let mut buf = [0u8; 777];
+        loop {
+            let n = decoder.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(out, decode_all(&frame).unwrap(), "{name}");
+    }
+}
+
+#[test]
+fn dictionary_ids_are_rejected() {
+    let frame = fs::read(corpus_dir().join("dictid.lz4")).unwrap();
+    assert!(matches!(
+        decode_all(&frame),
+        Err(Error::DictionaryNotSupported)
+    ));
+}
+
+fn xxh32(data: &[u8]) -> u32 {
+    let mut hasher = twox_hash::XxHash32::with_seed(0);
+    hasher.write(data);
+    hasher.finish() as u32
+}
+
+fn u32_at(frame: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(frame[pos..pos + 4].try_into().unwrap())
+}
+
+/// Checks `frame` against the LZ4 frame format and the encoder's own rules, and returns the
+/// content of its blocks.
+fn check_frame_structure(frame: &[u8], info: &FrameInfo, input: &[u8]) -> Vec<u8> {
+    assert_eq!(u32_at(frame, 0), 0x184D2204, "magic number");
+    let flg = frame[4];
+    assert_eq!(flg >> 6, 0b01, "version");
+    assert_eq!(
+        flg & 0x20 != 0,
+        info.block_mode == BlockMode::Independent,
+        "block independence flag"
+    );
+    assert_eq!(flg & 0x10 != 0, info.block_checksums, "block checksum flag");
+    assert_eq!(
+        flg & 0x08 != 0,
+        info.content_size.is_some(),
+        "content size flag"
+    );
+    assert_eq!(
+        flg & 0x04 != 0,
+        info.content_checksum,
+        "content checksum flag"
+    );
+    assert_eq!(flg & 0x03, 0, "reserved bit and dictionary ID flag");
+
+    let bd = frame[5];
+    assert_eq!(bd & 0x8F, 0, "reserved BD bits");
+    let block_size_id = bd >> 4;
+    assert!(
+        (4..=7).contains(&block_size_id),
+        "block size ID {block_size_id}"
+    );
+    if info.block_size != BlockSize::Auto {
+        assert_eq!(block_size_id, info.block_size as u8, "block size ID");
+    }
+    let max_block_size = 1usize << (8 + 2 * block_size_id as usize);
+
+    let mut pos = 6;
+    if let Some(content_size) = info.content_size {
+        let stored = u64::from_le_bytes(frame[pos..pos + 8].try_into().unwrap());
+        assert_eq!(stored, content_size, "content size");
+        pos += 8;
+    }
+    assert_eq!(
+        frame[pos],
+        (xxh32(&frame[4..pos]) >> 8) as u8,
+        "header checksum"
+    );
+    pos += 1;
+
+    let mut content = Vec::new();
+    let mut block_lens = Vec::new();
+    loop {
+        let block_info = u32_at(frame, pos);
+        pos += 4;
+        if block_info == 0 {
+            break;
+        }
+        let len = (block_info & 0x7FFF_FFFF) as usize;
+        assert!(len <= max_block_size, "block of {len} bytes");
+        let data = &frame[pos..pos + len];
+        pos += len;
+        if info.block_checksums {
+            assert_eq!(u32_at(frame, pos), xxh32(data), "block checksum");
+            pos += 4;
+        }
+
+        let start = content.len();
+        if block_info & 0x8000_0000 != 0 {
+            content.extend_from_slice(data);
+        } else {
+            let dict_start = match info.block_mode {
+                BlockMode::Linked => start.saturating_sub(64 * 1024),
+                BlockMode::Independent => start,
+            };
+            let block =
+                lz4_flex::block::decompress_with_dict(data, max_block_size, &content[dict_start..])
+                    .expect("block decodes");
+            assert!(
+                len < block.len(),
+                "compressed block is smaller than its content"
+            );
+            content.extend_from_slice(&block);
+        }
+        block_lens.push(content.len() - start);
+    }
+    if info.content_checksum {
+        assert_eq!(u32_at(frame, pos), xxh32(input), "content checksum");
+        pos += 4;
+    }
+    assert_eq!(pos, frame.len(), "no bytes after the frame");
+
+    // Blocks are filled up to the block size; only the last one may be shorter.
+    if let Some((_, full)) = block_lens.split_last() {
+        assert!(
+            full.iter().all(|&len| len == max_block_size),
+            "{block_lens:?}"
+        );
+    }
+    assert!(block_lens.iter().all(|&len| len != 0), "no empty blocks");
+    content
+}
+
+fn sample_inputs() -> Vec<Vec<u8>> {
+    let text =
+        fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("bench-data/text_50kb.txt")).unwrap();
+    let mut big = Vec::new();
+    for i in 0..300usize {
+        let start = (i * 523) % (text.len() - 64);
+        big.extend_from_slice(&text[start..start + 64]);
+        big.extend(std::iter::repeat((i % 251) as u8).take(4032));
+    }
+    let noise: Vec<u8> = (0..70_000u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+        .collect();
+    vec![Vec::new(), b"tiny".to_vec(), text, big, noise]
+}
+
+fn frame_infos(len: usize) -> Vec<FrameInfo> {
+    let mut infos = Vec::new();
+    for block_size in [
+        BlockSize::Auto,
+        BlockSize::Max64KB,
+        BlockSize::Max256KB,
+        BlockSize::Max1MB,
+    ] {
+        for block_mode in [BlockMode::Independent, BlockMode::Linked] {
+            for flags in 0..8u8 {
+                infos.push(
+                    FrameInfo::new()
+                        .block_size(block_size)
+                        .block_mode(block_mode)
+                        .block_checksums(flags & 1 != 0)
+                        .content_checksum(flags & 2 != 0)
+                        .content_size((flags & 4 != 0).then_some(len as u64)),
+                );
+            }
+        }
+    }
+    infos
+}
+
+fn encode(info: &FrameInfo, input: &[u8]) -> Vec<u8> {
+    let mut encoder = FrameEncoder::with_frame_info(info.clone(), Vec::new());
+    encoder.write_all(input).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn encoder_output_follows_frame_format() {
+    for input in sample_inputs() {
+        for info in frame_infos(input.len()) {
+            let frame = encode(&info, &input);
+            let content = check_frame_structure(&frame, &info, &input);
+            assert_eq!(content, input, "{info:?}");
+        }
+    }
+}
+
+/// Decodes our frames with the reference CLI, if it is installed.
+#[test]
+fn reference_cli_decodes_encoder_output() {
+    if Command::new("lz4").arg("-V").output().is_err() {
+        eprintln!("lz4 CLI not found, skipping");
+        return;
+    }
+    for input in sample_inputs() {
+        for info in frame_infos(input.len()).into_iter().step_by(3) {
+            let frame = encode(&info, &input);
+            let mut child = Command::new("lz4")
+                .args(["-d", "-c", "-q"])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            let mut stdin = child.stdin.take().unwrap();
+            let writer = std::thread::spawn(move || stdin.write_all(&frame));
+            let output = child.wait_with_output().unwrap();
+            writer.join().unwrap().unwrap();
+            assert!(output.status.success(), "{info:?}");
+            assert_eq!(output.stdout, input, "{info:?}");
+        }
+    }
+}
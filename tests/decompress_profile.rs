@@ -0,0 +1,136 @@
+//! The `decompress-prof` counters, per thread and for the whole process.
+#![cfg(all(feature = "decompress-prof", feature = "std", feature = "block"))]
+
+use lz4_flex_wasm_simd::block::{
+    compress, compress_with_dict, decompress, decompress_with_dict, read_decompress_profile,
+    read_thread_decompress_profile, reset_thread_decompress_profile, DecompressProfileSnapshot,
+};
+
+const JSON: &[u8] = include_bytes!("../bench-data/json_50kb.json");
+const TEXT: &[u8] = include_bytes!("../bench-data/text_50kb.txt");
+
+fn profile_of(decode: impl FnOnce()) -> DecompressProfileSnapshot {
+    reset_thread_decompress_profile();
+    decode();
+    read_thread_decompress_profile()
+}
+
+#[test]
+fn thread_counters_cover_the_decoded_bytes() {
+    let mut runs = TEXT[..5000].to_vec();
+    for len in 1..40 {
+        runs.resize(runs.len() + len * 7, b'a' + len as u8 % 3);
+        runs.extend_from_slice(&TEXT[len * 100..len * 100 + len]);
+    }
+
+    for data in [JSON, TEXT, &runs] {
+        let compressed = compress(data);
+        let profile = profile_of(|| {
+            assert_eq!(decompress(&compressed, data.len()).unwrap(), data);
+        });
+        assert_eq!(
+            profile.literal_bytes + profile.match_bytes,
+            data.len() as u64
+        );
+        assert!(profile.fast_token_hits > 0);
+        assert_eq!(profile.copy_from_dict_calls, 0);
+        #[cfg(not(feature = "safe-decode"))]
+        assert!(
+            profile.duplicate_nonoverlap_wild
+                + profile.duplicate_near_end_exact_nonoverlap
+                + profile.duplicate_overlap_small_u64
+                + profile.duplicate_overlap_small_simd
+                + profile.duplicate_overlap_large_offset_chunk
+                + profile.duplicate_overlap_fallback_byte
+                > 0
+        );
+    }
+
+    let (dict, data) = JSON.split_at(30_000);
+    let compressed = compress_with_dict(data, dict);
+    let profile = profile_of(|| {
+        assert_eq!(
+            decompress_with_dict(&compressed, data.len(), dict).unwrap(),
+            data
+        );
+    });
+    assert!(profile.copy_from_dict_calls > 0);
+    assert_eq!(
+        profile.literal_bytes + profile.match_bytes,
+        data.len() as u64
+    );
+
+    // A reset leaves the thread counters at zero.
+    reset_thread_decompress_profile();
+    assert_eq!(
+        read_thread_decompress_profile(),
+        DecompressProfileSnapshot::default()
+    );
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn threads_count_separately_and_add_up_to_the_process() {
+    let compressed = compress(TEXT);
+    // Other tests decode at the same time, so the process counters only grow by at least the
+    // work of these threads.
+    let before = read_decompress_profile();
+    reset_thread_decompress_profile();
+
+    let threads: Vec<_> = (1..=4)
+        .map(|iterations| {
+            let compressed = compressed.clone();
+            std::thread::spawn(move || {
+                profile_of(|| {
+                    for _ in 0..iterations {
+                        decompress(&compressed, TEXT.len()).unwrap();
+                    }
+                })
+            })
+        })
+        .collect();
+    let mut total = DecompressProfileSnapshot::default();
+    for (iterations, thread) in (1..=4).zip(threads) {
+        let profile = thread.join().unwrap();
+        assert_eq!(
+            profile.literal_bytes + profile.match_bytes,
+            iterations * TEXT.len() as u64
+        );
+        total += &profile;
+    }
+    assert_eq!(
+        total.literal_bytes + total.match_bytes,
+        10 * TEXT.len() as u64
+    );
+
+    assert_eq!(
+        read_thread_decompress_profile(),
+        DecompressProfileSnapshot::default()
+    );
+    let after = read_decompress_profile();
+    assert!(after.literal_bytes - before.literal_bytes >= total.literal_bytes);
+    assert!(after.match_bytes - before.match_bytes >= total.match_bytes);
+    assert!(after.fast_token_hits - before.fast_token_hits >= total.fast_token_hits);
+}
+
+#[cfg(feature = "frame")]
+#[test]
+fn frame_decoder_counts_its_blocks() {
+    use std::io::{Read, Write};
+
+    let data = TEXT.repeat(3);
+    let mut encoder = lz4_flex_wasm_simd::frame::FrameEncoder::new(Vec::new());
+    encoder.write_all(&data).unwrap();
+    let frame = encoder.finish().unwrap();
+    let profile = profile_of(|| {
+        let mut out = Vec::new();
+        lz4_flex_wasm_simd::frame::FrameDecoder::new(&frame[..])
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, data);
+    });
+    assert_eq!(
+        profile.literal_bytes + profile.match_bytes,
+        data.len() as u64
+    );
+}
@@ -2,6 +2,10 @@
 
 use std::io::{Read, Write};
 
+use proptest::prelude::*;
+
+mod common;
+
 #[test]
 fn frame_roundtrip_local() {
     let data = b"frame payload with enough bytes for checksum and block splitting";
@@ -35,3 +39,362 @@ fn frame_cross_compat_with_lz4_flex() {
     dec2.read_to_end(&mut out2).expect("read upstream->local");
     assert_eq!(out2, data);
 }
+
+#[test]
+fn memory_usage_frames_cross_decode() {
+    use lz4_flex_wasm_simd::frame::{BlockMode, FrameEncoder, FrameInfo};
+    let mut data = include_bytes!("../bench-data/text_50kb.txt").repeat(3);
+    data.extend_from_slice(include_bytes!("../bench-data/json_50kb.json"));
+
+    let encode_default = |info: FrameInfo| {
+        let mut enc = FrameEncoder::with_frame_info(info, Vec::new());
+        enc.write_all(&data).unwrap();
+        enc.finish().unwrap()
+    };
+    let encode_large = |info: FrameInfo| {
+        let mut enc = FrameEncoder::<_, 17>::with_memory_usage(info, Vec::new());
+        enc.write_all(&data).unwrap();
+        enc.finish().unwrap()
+    };
+    for mode in [BlockMode::Independent, BlockMode::Linked] {
+        let info = FrameInfo::new().block_mode(mode);
+        let large = encode_large(info.clone());
+        assert!(large.len() < encode_default(info).len());
+        let mut out = Vec::new();
+        lz4_flex::frame::FrameDecoder::new(&large[..])
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, data);
+    }
+}
+
+#[test]
+fn decompression_error_reports_block_position() {
+    use lz4_flex_wasm_simd::frame::{BlockMode, BlockSize, DecompressError, Error, FrameInfo};
+
+    let data: Vec<u8> = (0..150_000u32)
+        .map(|i| (i % 251) as u8 ^ (i / 509) as u8)
+        .collect();
+    let info = FrameInfo::new()
+        .block_size(BlockSize::Max64KB)
+        .block_mode(BlockMode::Independent);
+    let mut enc = lz4_flex_wasm_simd::frame::FrameEncoder::with_frame_info(info, Vec::new());
+    enc.write_all(&data).expect("write");
+    let mut compressed = enc.finish().expect("finish");
+
+    // Frame header is 7 bytes without a content size; each block has a 4 byte size prefix.
+    let block_len =
+        |pos: usize| u32::from_le_bytes(compressed[pos..pos + 4].try_into().unwrap()) as usize;
+    assert_eq!(block_len(7) & 0x8000_0000, 0, "first block is compressed");
+    let second = 7 + 4 + block_len(7);
+    let second_len = block_len(second);
+    assert_eq!(second_len & 0x8000_0000, 0, "second block is compressed");
+    // A literal length continuation that never ends.
+    compressed[second + 4..second + 4 + second_len].fill(0xFF);
+
+    let mut out = Vec::new();
+    let err = lz4_flex_wasm_simd::frame::FrameDecoder::new(&compressed[..])
+        .read_to_end(&mut out)
+        .expect_err("corrupted block");
+    match Error::from(err) {
+        Error::DecompressionError {
+            error:
+                DecompressError::ExpectedAnotherByte {
+                    input_pos: 0,
+                    output_pos: 0,
+                },
+            block_index: 1,
+            frame_offset,
+        } => assert_eq!(frame_offset, second as u64),
+        other => panic!("unexpected error {other:?}"),
+    }
+}
+
+#[test]
+fn encoder_stats_cover_every_block() {
+    use lz4_flex_wasm_simd::frame::{BlockMode, BlockSize, FrameEncoder, FrameInfo};
+
+    let mut data = include_bytes!("../bench-data/text_50kb.txt").repeat(3);
+    data.extend_from_slice(include_bytes!("../bench-data/json_50kb.json"));
+    for mode in [BlockMode::Independent, BlockMode::Linked] {
+        let info = FrameInfo::new()
+            .block_size(BlockSize::Max64KB)
+            .block_mode(mode);
+        let mut enc = FrameEncoder::with_frame_info(info.clone(), Vec::new());
+        assert!(enc.stats().is_none());
+        enc.enable_stats();
+        enc.write_all(&data).unwrap();
+        enc.flush().unwrap();
+        let stats = enc.stats().unwrap().clone();
+        let compressed = enc.finish().unwrap();
+        assert_eq!(stats.blocks, data.chunks(64 * 1024).len() as u64);
+        assert_eq!(stats.input_bytes, data.len() as u64);
+        assert_eq!(stats.literal_bytes + stats.match_bytes, data.len() as u64);
+        assert!(stats.output_bytes < compressed.len() as u64);
+        assert_eq!(stats.match_lengths.iter().sum::<u64>(), stats.matches);
+
+        // Statistics do not change the frame.
+        let mut plain = FrameEncoder::with_frame_info(info, Vec::new());
+        plain.write_all(&data).unwrap();
+        assert_eq!(plain.finish().unwrap(), compressed);
+    }
+}
+
+/// A writer that remembers how much of its output was flushed.
+#[derive(Default)]
+struct FlushRecorder {
+    data: Vec<u8>,
+    flushed: Vec<usize>,
+}
+
+impl Write for FlushRecorder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.flushed.push(self.data.len());
+        Ok(())
+    }
+}
+
+/// Decodes the complete blocks of a frame that may not be finished yet.
+fn decode_prefix(frame: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let _ = lz4_flex_wasm_simd::frame::FrameDecoder::new(frame).read_to_end(&mut out);
+    out
+}
+
+#[test]
+fn encoder_flush_reaches_the_inner_writer() {
+    use lz4_flex_wasm_simd::frame::FrameEncoder;
+
+    let line = b"2026-10-18T12:00:00Z INFO request served in 3ms\n";
+    let mut enc = FrameEncoder::new(std::io::BufWriter::new(Vec::new()));
+    enc.write_all(line).unwrap();
+    // A block that stays in the BufWriter.
+    enc.flush_block().unwrap();
+    assert!(enc.get_ref().get_ref().is_empty());
+    assert!(!enc.get_ref().buffer().is_empty());
+    enc.write_all(line).unwrap();
+    enc.flush().unwrap();
+    assert!(enc.get_ref().buffer().is_empty());
+    assert_eq!(decode_prefix(enc.get_ref().get_ref()), line.repeat(2));
+
+    // `sync_flush` without pending data still flushes the writer.
+    let mut enc = FrameEncoder::new(FlushRecorder::default());
+    enc.sync_flush().unwrap();
+    enc.write_all(line).unwrap();
+    enc.sync_flush().unwrap();
+    enc.sync_flush().unwrap();
+    let flushed = enc.get_ref().flushed.clone();
+    assert_eq!(flushed[0], 0);
+    assert_eq!(flushed[1], flushed[2]);
+    // Finishing writes the end mark without another flush.
+    let recorder = enc.finish().unwrap();
+    assert_eq!(recorder.flushed.len(), 3);
+    assert_eq!(decode_prefix(&recorder.data), line);
+}
+
+#[test]
+fn encoder_auto_flush_by_bytes_and_ticks() {
+    use lz4_flex_wasm_simd::frame::{AutoFlush, BlockMode, FrameEncoder, FrameInfo};
+
+    let data = include_bytes!("../bench-data/json_50kb.json");
+    for mode in [BlockMode::Independent, BlockMode::Linked] {
+        let info = FrameInfo::new().block_mode(mode);
+        let mut enc = FrameEncoder::with_frame_info(info.clone(), FlushRecorder::default());
+        enc.set_auto_flush(AutoFlush::new().bytes(1000));
+        let mut written = 0;
+        for chunk in data[..20_000].chunks(333) {
+            enc.write_all(chunk).unwrap();
+            written += chunk.len();
+            // Every flush point is a sync point, and at most 1000 bytes wait.
+            let recorder = enc.get_ref();
+            let flushed = *recorder.flushed.last().unwrap_or(&0);
+            let decoded = decode_prefix(&recorder.data[..flushed]);
+            assert_eq!(decoded.len(), written / 1000 * 1000);
+            assert_eq!(decoded, data[..decoded.len()]);
+        }
+        let recorder = enc.finish().unwrap();
+        assert_eq!(recorder.flushed.len(), 20);
+        let mut out = Vec::new();
+        lz4_flex::frame::FrameDecoder::new(&recorder.data[..])
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, data[..20_000]);
+
+        let mut enc = FrameEncoder::with_frame_info(info, FlushRecorder::default());
+        // Without a tick policy ticks do nothing.
+        enc.write_all(&data[..100]).unwrap();
+        enc.tick().unwrap();
+        assert!(enc.get_ref().flushed.is_empty());
+        enc.set_auto_flush(AutoFlush::new().ticks(2));
+        enc.tick().unwrap();
+        assert!(enc.get_ref().flushed.is_empty());
+        enc.tick().unwrap();
+        assert_eq!(enc.get_ref().flushed.len(), 1);
+        assert_eq!(decode_prefix(&enc.get_ref().data), data[..100]);
+        // Ticks without pending data do not count.
+        enc.tick().unwrap();
+        enc.write_all(&data[100..200]).unwrap();
+        enc.tick().unwrap();
+        assert_eq!(enc.get_ref().flushed.len(), 1);
+        // Nor do ticks before an explicit flush.
+        enc.flush().unwrap();
+        enc.write_all(&data[200..300]).unwrap();
+        enc.tick().unwrap();
+        assert_eq!(enc.get_ref().flushed.len(), 2);
+        enc.tick().unwrap();
+        assert_eq!(enc.get_ref().flushed.len(), 3);
+        assert_eq!(decode_prefix(&enc.finish().unwrap().data), data[..300]);
+    }
+}
+
+/// Frame settings as (block size index, linked, block checksums, content checksum, content size).
+type Settings = (usize, bool, bool, bool, bool);
+
+fn settings() -> impl Strategy<Value = Settings> {
+    (
+        0usize..5,
+        any::<bool>(),
+        any::<bool>(),
+        any::<bool>(),
+        any::<bool>(),
+    )
+}
+
+fn our_frame_info(settings: Settings, len: usize) -> lz4_flex_wasm_simd::frame::FrameInfo {
+    use lz4_flex_wasm_simd::frame::{BlockMode, BlockSize, FrameInfo};
+    let (size, linked, block_checksums, content_checksum, content_size) = settings;
+    let block_size = [
+        BlockSize::Auto,
+        BlockSize::Max64KB,
+        BlockSize::Max256KB,
+        BlockSize::Max1MB,
+        BlockSize::Max4MB,
+    ][size];
+    FrameInfo::new()
+        .block_size(block_size)
+        .block_mode(if linked {
+            BlockMode::Linked
+        } else {
+            BlockMode::Independent
+        })
+        .block_checksums(block_checksums)
+        .content_checksum(content_checksum)
+        .content_size(content_size.then_some(len as u64))
+}
+
+fn their_frame_info(settings: Settings, len: usize) -> lz4_flex::frame::FrameInfo {
+    use lz4_flex::frame::{BlockMode, BlockSize, FrameInfo};
+    let (size, linked, block_checksums, content_checksum, content_size) = settings;
+    let block_size = [
+        BlockSize::Auto,
+        BlockSize::Max64KB,
+        BlockSize::Max256KB,
+        BlockSize::Max1MB,
+        BlockSize::Max4MB,
+    ][size];
+    FrameInfo::new()
+        .block_size(block_size)
+        .block_mode(if linked {
+            BlockMode::Linked
+        } else {
+            BlockMode::Independent
+        })
+        .block_checksums(block_checksums)
+        .content_checksum(content_checksum)
+        .content_size(content_size.then_some(len as u64))
+}
+
+/// Writes `data` in pieces of the given sizes (cycled), then the rest in one call.
+fn write_chunked(w: &mut impl Write, data: &[u8], chunks: &[usize]) {
+    let mut rest = data;
+    for &chunk in chunks.iter().cycle().take(64) {
+        let (head, tail) = rest.split_at(chunk.min(rest.len()));
+        w.write_all(head).unwrap();
+        rest = tail;
+    }
+    w.write_all(rest).unwrap();
+}
+
+/// Reads until EOF with buffers of the given sizes (cycled).
+fn read_chunked(r: &mut impl Read, chunks: &[usize]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut buf = vec![0u8; chunks.iter().copied().max().unwrap_or(1).max(1)];
+    for &chunk in chunks.iter().cycle() {
+        let n = r.read(&mut buf[..chunk.max(1)]).unwrap();
+        if n == 0 {
+            break;
+        }
+        out.extend_from_slice(&buf[..n]);
+    }
+    out
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(48))]
+
+    #[test]
+    fn frame_info_combinations_cross_decode(
+        data in common::data_shapes(12, 20_000),
+        settings in settings(),
+        chunks in proptest::collection::vec(0usize..40_000, 1..8),
+    ) {
+        let mut enc = lz4_flex_wasm_simd::frame::FrameEncoder::with_frame_info(
+            our_frame_info(settings, data.len()),
+            Vec::new(),
+        );
+        write_chunked(&mut enc, &data, &chunks);
+        let ours = enc.finish().unwrap();
+
+        let mut out = Vec::new();
+        lz4_flex::frame::FrameDecoder::new(&ours[..]).read_to_end(&mut out).unwrap();
+        prop_assert_eq!(&out, &data);
+        let mut out = Vec::new();
+        lz4_flex_wasm_simd::frame::FrameDecoder::new(&ours[..])
+            .read_to_end(&mut out)
+            .unwrap();
+        prop_assert_eq!(&out, &data);
+
+        let mut enc = lz4_flex::frame::FrameEncoder::with_frame_info(
+            their_frame_info(settings, data.len()),
+            Vec::new(),
+        );
+        write_chunked(&mut enc, &data, &chunks);
+        let theirs = enc.finish().unwrap();
+        let mut dec = lz4_flex_wasm_simd::frame::FrameDecoder::new(&theirs[..]);
+        prop_assert_eq!(read_chunked(&mut dec, &chunks), data);
+    }
+
+    #[test]
+    fn linked_frames_with_chunked_io(
+        data in common::data_shapes(24, 30_000),
+        write_chunks in proptest::collection::vec(0usize..70_000, 1..8),
+        read_chunks in proptest::collection::vec(1usize..5_000, 1..8),
+    ) {
+        use lz4_flex_wasm_simd::frame::{BlockMode, BlockSize, FrameInfo};
+        let info = FrameInfo::new()
+            .block_size(BlockSize::Max64KB)
+            .block_mode(BlockMode::Linked);
+        let mut enc = lz4_flex_wasm_simd::frame::FrameEncoder::with_frame_info(info, Vec::new());
+        write_chunked(&mut enc, &data, &write_chunks);
+        let ours = enc.finish().unwrap();
+
+        let mut dec = lz4_flex::frame::FrameDecoder::new(&ours[..]);
+        prop_assert_eq!(read_chunked(&mut dec, &read_chunks), &data[..]);
+        let mut dec = lz4_flex_wasm_simd::frame::FrameDecoder::new(&ours[..]);
+        prop_assert_eq!(read_chunked(&mut dec, &read_chunks), &data[..]);
+
+        let info = lz4_flex::frame::FrameInfo::new()
+            .block_size(lz4_flex::frame::BlockSize::Max64KB)
+            .block_mode(lz4_flex::frame::BlockMode::Linked);
+        let mut enc = lz4_flex::frame::FrameEncoder::with_frame_info(info, Vec::new());
+        write_chunked(&mut enc, &data, &write_chunks);
+        let theirs = enc.finish().unwrap();
+        let mut dec = lz4_flex_wasm_simd::frame::FrameDecoder::new(&theirs[..]);
+        prop_assert_eq!(read_chunked(&mut dec, &read_chunks), data);
+    }
+}
@@ -1,6 +1,7 @@
 use std::hash::Hasher;
 
-use lz4_flex_wasm_simd::hash::XxHash32;
+use lz4_flex_wasm_simd::hash::{XxHash32, XxHash3_128, XxHash3_64, XxHash64};
+use proptest::prelude::*;
 
 #[test]
 fn xxhash32_matches_twox_hash_oneshot() {
@@ -26,3 +27,74 @@ fn xxhash32_streaming_matches_twox_hash_streaming() {
 
     assert_eq!(a.finish_32(), b.finish_32());
 }
+
+proptest! {
+    #[test]
+    fn xxhash32_matches_twox_hash_random_splits(
+        data in proptest::collection::vec(any::<u8>(), 0..2048),
+        seed in any::<u32>(),
+        splits in proptest::collection::vec(any::<usize>(), 0..6),
+    ) {
+        let mut cuts: Vec<usize> = splits.iter().map(|s| s % (data.len() + 1)).collect();
+        cuts.sort_unstable();
+
+        let mut a = XxHash32::with_seed(seed);
+        let mut b = twox_hash::XxHash32::with_seed(seed);
+        let mut start = 0;
+        for cut in cuts.into_iter().chain([data.len()]) {
+            a.write(&data[start..cut]);
+            b.write(&data[start..cut]);
+            start = cut;
+        }
+
+        prop_assert_eq!(a.finish_32(), b.finish_32());
+        prop_assert_eq!(XxHash32::oneshot(seed, &data), twox_hash::XxHash32::oneshot(seed, &data));
+    }
+
+    #[test]
+    fn xxhash32_oneshot_many_matches_twox_hash(
+        buffers in proptest::collection::vec(proptest::collection::vec(any::<u8>(), 0..300), 0..11),
+        seed in any::<u32>(),
+    ) {
+        let inputs: Vec<&[u8]> = buffers.iter().map(|b| b.as_slice()).collect();
+        let mut out = vec![0u32; inputs.len()];
+        XxHash32::oneshot_many(seed, &inputs, &mut out);
+        for (input, digest) in inputs.iter().zip(out) {
+            prop_assert_eq!(digest, twox_hash::XxHash32::oneshot(seed, input));
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn xxhash64_and_xxh3_match_twox_hash_random_splits(
+        data in proptest::collection::vec(any::<u8>(), 0..3000),
+        seed in any::<u64>(),
+        splits in proptest::collection::vec(any::<usize>(), 0..6),
+    ) {
+        let mut cuts: Vec<usize> = splits.iter().map(|s| s % (data.len() + 1)).collect();
+        cuts.sort_unstable();
+
+        let mut h64 = XxHash64::with_seed(seed);
+        let mut h3_64 = XxHash3_64::with_seed(seed);
+        let mut h3_128 = XxHash3_128::with_seed(seed);
+        let mut start = 0;
+        for cut in cuts.into_iter().chain([data.len()]) {
+            h64.write(&data[start..cut]);
+            h3_64.write(&data[start..cut]);
+            h3_128.write(&data[start..cut]);
+            start = cut;
+        }
+
+        prop_assert_eq!(h64.finish(), twox_hash::XxHash64::oneshot(seed, &data));
+        prop_assert_eq!(XxHash64::oneshot(seed, &data), twox_hash::XxHash64::oneshot(seed, &data));
+
+        let expected_64 = twox_hash::XxHash3_64::oneshot_with_seed(seed, &data);
+        prop_assert_eq!(h3_64.finish(), expected_64);
+        prop_assert_eq!(XxHash3_64::oneshot(seed, &data), expected_64);
+
+        let expected_128 = twox_hash::XxHash3_128::oneshot_with_seed(seed, &data);
+        prop_assert_eq!(h3_128.finish_128(), expected_128);
+        prop_assert_eq!(XxHash3_128::oneshot(seed, &data), expected_128);
+    }
+}
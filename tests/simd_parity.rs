@@ -0,0 +1,115 @@
+#![cfg(all(
+    feature = "block",
+    any(
+        all(target_arch = "wasm32", target_feature = "simd128"),
+        target_arch = "x86_64",
+        target_arch = "aarch64"
+    )
+))]
+
+//! SIMD kernels against the scalar paths. `lz4_flex` is built with `safe-decode` and
+//! `safe-encode`, so it serves as the scalar reference for whole blocks.
+
+use lz4_flex_wasm_simd::block::{compress, decompress, decompress_into};
+use lz4_flex_wasm_simd::simd::{count_same_bytes_scalar, count_same_bytes_simd};
+
+fn pseudo_random(len: usize, mut seed: u32) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) as u8
+        })
+        .collect()
+}
+
+/// A block of `literals` followed by one match of `match_length` at `offset` and a final
+/// empty sequence.
+fn single_match_block(literals: &[u8], offset: u16, match_length: usize) -> Vec<u8> {
+    fn push_len(out: &mut Vec<u8>, mut len: usize) {
+        len -= 15;
+        while len >= 255 {
+            out.push(255);
+            len -= 255;
+        }
+        out.push(len as u8);
+    }
+
+    let lit_nibble = literals.len().min(15) as u8;
+    let match_nibble = (match_length - 4).min(15) as u8;
+    let mut out = vec![(lit_nibble << 4) | match_nibble];
+    if lit_nibble == 15 {
+        push_len(&mut out, literals.len());
+    }
+    out.extend_from_slice(literals);
+    out.extend_from_slice(&offset.to_le_bytes());
+    if match_nibble == 15 {
+        push_len(&mut out, match_length - 4);
+    }
+    out.push(0);
+    out
+}
+
+#[test]
+fn count_same_bytes_matches_scalar() {
+    let a = pseudo_random(300, 7);
+    for start in 0..20 {
+        for mismatch in (start..a.len()).step_by(3) {
+            let mut b = a.clone();
+            b[mismatch] = !b[mismatch];
+            assert_eq!(
+                count_same_bytes_simd(&a[start..], &b[start..]),
+                count_same_bytes_scalar(&a[start..], &b[start..]),
+                "start={start} mismatch={mismatch}"
+            );
+        }
+    }
+}
+
+#[test]
+fn overlapping_matches_match_reference_decoder() {
+    for offset in 1usize..=40 {
+        let literals = pseudo_random(offset, offset as u32);
+        for match_length in [4usize, 15, 16, 17, 18, 19, 31, 32, 33, 64, 100, 300] {
+            let block = single_match_block(&literals, offset as u16, match_length);
+            let len = offset + match_length;
+            let expected = lz4_flex::block::decompress(&block, len).unwrap();
+
+            assert_eq!(
+                decompress(&block, len).unwrap(),
+                expected,
+                "offset={offset} match_length={match_length}"
+            );
+
+            // An exactly sized output keeps the copies on the near-end paths.
+            let mut out = vec![0u8; len];
+            assert_eq!(decompress_into(&block, &mut out).unwrap(), len);
+            assert_eq!(out, expected, "offset={offset} match_length={match_length}");
+        }
+    }
+}
+
+#[test]
+fn compressed_output_decodes_with_reference() {
+    let mut inputs = vec![
+        pseudo_random(10_000, 1),
+        include_bytes!("../bench-data/text_50kb.txt").to_vec(),
+        include_bytes!("../bench-data/json_50kb.json").to_vec(),
+    ];
+    // Long matches with short periods and a few mismatches to stop them.
+    for period in [1usize, 2, 3, 5, 8, 13, 16, 33] {
+        let mut data: Vec<u8> = (0..20_000).map(|i| (i % period) as u8).collect();
+        for i in (0..data.len()).step_by(997) {
+            data[i] ^= 0x55;
+        }
+        inputs.push(data);
+    }
+
+    for input in &inputs {
+        let compressed = compress(input);
+        let restored = lz4_flex::block::decompress(&compressed, input.len()).unwrap();
+        assert_eq!(&restored, input);
+
+        let reference = lz4_flex::block::compress(input);
+        assert_eq!(&decompress(&reference, input.len()).unwrap(), input);
+    }
+}